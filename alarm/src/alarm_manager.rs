@@ -1,25 +1,291 @@
-use crate::{Armable, Triggerable};
+use crate::{Armable, Schedulable, Triggerable};
 extern crate alloc;
+use alloc::vec;
 use alloc::vec::Vec;
 use rp_pico::hal::rtc::DateTime;
+
+/// Number of failed triggers in a row after which an alarm is disarmed.
+pub const DEFAULT_MAX_CONSECUTIVE_FAILURES: u32 = 3;
+
+/// Failure reported by `AlarmManager::trigger_all`.
+#[derive(Debug, PartialEq)]
+pub struct AlarmFailure<E> {
+    pub alarm_index: usize,
+    pub error: E,
+    // True if the alarm has been disarmed until its next activation period
+    pub degraded: bool,
+}
+
 pub struct AlarmManager<T: Triggerable + Armable> {
     alarms: Vec<T>,
+    consecutive_failures: Vec<u32>,
+    max_consecutive_failures: u32,
 }
 
 impl<T: Triggerable + Armable> AlarmManager<T> {
     pub fn new(alarms: Vec<T>) -> Self {
-        Self { alarms }
+        let consecutive_failures = vec![0; alarms.len()];
+        Self {
+            alarms,
+            consecutive_failures,
+            max_consecutive_failures: DEFAULT_MAX_CONSECUTIVE_FAILURES,
+        }
+    }
+
+    pub fn set_max_consecutive_failures(&mut self, max_consecutive_failures: u32) {
+        self.max_consecutive_failures = max_consecutive_failures;
+    }
+
+    pub fn len(&self) -> usize {
+        self.alarms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.alarms.is_empty()
+    }
+
+    pub fn alarm(&self, index: usize) -> Option<&T> {
+        self.alarms.get(index)
+    }
+
+    /// Gives access to an alarm to edit it, its failure count starts over.
+    pub fn alarm_mut(&mut self, index: usize) -> Option<&mut T> {
+        if let Some(failures) = self.consecutive_failures.get_mut(index) {
+            *failures = 0;
+        }
+        self.alarms.get_mut(index)
     }
 
     pub fn rearm_all(&mut self, current_time: &DateTime) {
-        for alarm in &mut self.alarms {
-            alarm.rearm(current_time);
+        for (alarm, failures) in self.alarms.iter_mut().zip(&mut self.consecutive_failures) {
+            if alarm.rearm(current_time) {
+                *failures = 0;
+            }
+        }
+    }
+
+    /// Triggers every alarm and returns the ones that failed.
+    ///
+    /// A failing alarm stays active so it is retried on the next call. Once it
+    /// has failed `max_consecutive_failures` times in a row it is disarmed
+    /// until `rearm_all` sees it outside of its activation period.
+    pub fn trigger_all(&mut self, current_time: &DateTime) -> Vec<AlarmFailure<T::Error>> {
+        let mut failures = Vec::new();
+        for (alarm_index, (alarm, consecutive_failures)) in self
+            .alarms
+            .iter_mut()
+            .zip(&mut self.consecutive_failures)
+            .enumerate()
+        {
+            match alarm.trigger(current_time) {
+                Ok(_) => *consecutive_failures = 0,
+                Err(error) => {
+                    *consecutive_failures += 1;
+                    let degraded = *consecutive_failures >= self.max_consecutive_failures;
+                    if degraded {
+                        alarm.disarm();
+                    }
+                    failures.push(AlarmFailure {
+                        alarm_index,
+                        error,
+                        degraded,
+                    });
+                }
+            }
         }
+        failures
+    }
+}
+
+impl<T: Triggerable + Armable + Schedulable> AlarmManager<T> {
+    /// Index of the alarm starting first and the seconds until it does.
+    pub fn next_alarm(&self, current_time: &DateTime) -> Option<(usize, u32)> {
+        self.alarms
+            .iter()
+            .enumerate()
+            .filter_map(|(index, alarm)| Some((index, alarm.seconds_until_next(current_time)?)))
+            .min_by_key(|&(_, seconds)| seconds)
+    }
+
+    /// Whether some alarm is in its activation period and has not been stopped.
+    pub fn is_any_ringing(&self, current_time: &DateTime) -> bool {
+        self.alarms
+            .iter()
+            .any(|alarm| alarm.is_ringing(current_time))
     }
 
-    pub fn trigger_all(&mut self, current_time: &DateTime) {
+    /// Indices of the alarms whose last activation period went unnoticed.
+    pub fn missed_alarms(&self) -> impl Iterator<Item = usize> + '_ {
+        self.alarms
+            .iter()
+            .enumerate()
+            .filter(|(_, alarm)| alarm.is_missed())
+            .map(|(index, _)| index)
+    }
+
+    pub fn clear_missed(&mut self) {
         for alarm in &mut self.alarms {
-            alarm.trigger(current_time);
+            alarm.clear_missed();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::alarm_manager::{AlarmFailure, AlarmManager};
+    use crate::{Alarm, AlarmError, WeeklyDate};
+    use arrayvec::ArrayString;
+    use callback_mock::{assert_called_times, MockClock, RecordingCallback, ScriptedCallback};
+    use rp_pico::hal::rtc::{DateTime, DayOfWeek};
+
+    fn monday_at(second: u8) -> DateTime {
+        DateTime {
+            year: 0,
+            month: 0,
+            day: 0,
+            day_of_week: DayOfWeek::Monday,
+            hour: 0,
+            minute: 0,
+            second,
         }
     }
+
+    #[test]
+    fn failing_alarm_is_retried_then_degraded() {
+        let clock = MockClock::new();
+        let callback = ScriptedCallback::new(clock.clone(), vec![Err(()), Err(()), Ok(true)]);
+        let callback_log = callback.log();
+        let alarm = Alarm::new(
+            WeeklyDate::new(DayOfWeek::Monday, 0, 0, 10),
+            ArrayString::<16>::from("descr").unwrap(),
+            30,
+            callback,
+            RecordingCallback::new(clock),
+        );
+        let mut manager = AlarmManager::new(vec![alarm]);
+        manager.set_max_consecutive_failures(2);
+
+        assert_eq!(
+            manager.trigger_all(&monday_at(20)),
+            vec![AlarmFailure {
+                alarm_index: 0,
+                error: AlarmError::Callback(()),
+                degraded: false
+            }]
+        );
+        assert_eq!(
+            manager.trigger_all(&monday_at(21)),
+            vec![AlarmFailure {
+                alarm_index: 0,
+                error: AlarmError::Callback(()),
+                degraded: true
+            }]
+        );
+        // Degraded alarms are not triggered until they get rearmed.
+        assert_eq!(manager.trigger_all(&monday_at(22)), vec![]);
+        assert_called_times(&callback_log, 2);
+
+        manager.rearm_all(&monday_at(0));
+        assert_eq!(manager.trigger_all(&monday_at(23)), vec![]);
+        assert_called_times(&callback_log, 3);
+    }
+
+    #[test]
+    fn editing_an_alarm_resets_its_failures() {
+        let clock = MockClock::new();
+        let callback = ScriptedCallback::new(clock.clone(), vec![Err(()), Err(()), Ok(true)]);
+        let callback_log = callback.log();
+        let alarm = Alarm::new(
+            WeeklyDate::new(DayOfWeek::Monday, 0, 0, 10),
+            ArrayString::<16>::from("descr").unwrap(),
+            30,
+            callback,
+            RecordingCallback::new(clock),
+        );
+        let mut manager = AlarmManager::new(vec![alarm]);
+        manager.set_max_consecutive_failures(2);
+
+        manager.trigger_all(&monday_at(20));
+        manager
+            .alarm_mut(0)
+            .unwrap()
+            .set_description(ArrayString::from("edited").unwrap());
+        // The second failure in a row since the edit is only the first one
+        assert_eq!(
+            manager.trigger_all(&monday_at(21)),
+            vec![AlarmFailure {
+                alarm_index: 0,
+                error: AlarmError::Callback(()),
+                degraded: false
+            }]
+        );
+        assert_eq!(manager.trigger_all(&monday_at(22)), vec![]);
+        assert_called_times(&callback_log, 3);
+        assert_eq!(manager.alarm(0).unwrap().description().as_str(), "edited");
+        assert_eq!(manager.len(), 1);
+    }
+
+    #[test]
+    fn next_alarm_is_the_closest_enabled_one() {
+        let clock = MockClock::new();
+        let alarm_at = |second| {
+            Alarm::new(
+                WeeklyDate::new(DayOfWeek::Monday, 0, 0, second),
+                ArrayString::<16>::from("descr").unwrap(),
+                5,
+                RecordingCallback::new(clock.clone()),
+                RecordingCallback::new(clock.clone()),
+            )
+        };
+        let mut manager = AlarmManager::new(vec![alarm_at(40), alarm_at(30)]);
+
+        assert_eq!(manager.next_alarm(&monday_at(20)), Some((1, 10)));
+        manager.alarm_mut(1).unwrap().set_enabled(false);
+        assert_eq!(manager.next_alarm(&monday_at(20)), Some((0, 20)));
+        manager.alarm_mut(0).unwrap().set_enabled(false);
+        assert_eq!(manager.next_alarm(&monday_at(20)), None);
+    }
+
+    #[test]
+    fn stopped_alarm_is_no_longer_ringing() {
+        let clock = MockClock::new();
+        let alarm = Alarm::new(
+            WeeklyDate::new(DayOfWeek::Monday, 0, 0, 10),
+            ArrayString::<16>::from("descr").unwrap(),
+            30,
+            ScriptedCallback::new(clock.clone(), vec![Ok::<_, ()>(false)]),
+            RecordingCallback::new(clock),
+        );
+        let mut manager = AlarmManager::new(vec![alarm]);
+
+        assert!(!manager.is_any_ringing(&monday_at(5)));
+        assert!(manager.is_any_ringing(&monday_at(20)));
+        manager.trigger_all(&monday_at(20));
+        // Still in its period, but acknowledged
+        assert_eq!(manager.next_alarm(&monday_at(21)), Some((0, 0)));
+        assert!(!manager.is_any_ringing(&monday_at(21)));
+        manager.alarm_mut(0).unwrap().set_enabled(false);
+        manager.rearm_all(&monday_at(50));
+        assert!(!manager.is_any_ringing(&monday_at(20)));
+    }
+
+    #[test]
+    fn missed_alarms_are_listed_until_cleared() {
+        let clock = MockClock::new();
+        let alarm = Alarm::new(
+            WeeklyDate::new(DayOfWeek::Monday, 0, 0, 10),
+            ArrayString::<16>::from("descr").unwrap(),
+            30,
+            RecordingCallback::new(clock.clone()),
+            RecordingCallback::new(clock),
+        );
+        let mut manager = AlarmManager::new(vec![alarm]);
+
+        manager.rearm_all(&monday_at(20));
+        manager.rearm_all(&monday_at(50));
+        assert_eq!(manager.missed_alarms().collect::<Vec<_>>(), vec![0]);
+
+        manager.clear_missed();
+        assert_eq!(manager.missed_alarms().count(), 0);
+    }
 }
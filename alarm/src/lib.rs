@@ -9,19 +9,64 @@ pub struct Alarm<C, D, DateFormat>  {
     date: DateFormat,
     description: ArrayString<16>,
     total_duration_sec: u32,
-    intense_duration_sec: u32,
-    pause_duration_sec: u32,
     callback: C,
     deactivation_callback: D,
-    is_active: bool
+    is_active: bool,
+    // Disabled alarms are never triggered
+    is_enabled: bool,
+    // Set by `rearm` when the activation period is left
+    was_in_period: bool,
+    // The last activation period ended without anybody stopping the alarm
+    is_missed: bool
 }
 
 impl<C, D, DateFormat> Alarm<C,D, DateFormat> {
-    pub fn new(date: DateFormat, description: ArrayString<16>, total_duration_sec: u32, intense_duration_sec: u32, pause_duration_sec: u32, action: C, deactivation_callback: D) -> Self {
-        Self { date, description, total_duration_sec, intense_duration_sec, pause_duration_sec, callback: action, deactivation_callback: deactivation_callback, is_active: true }
+    pub fn new(date: DateFormat, description: ArrayString<16>, total_duration_sec: u32, action: C, deactivation_callback: D) -> Self {
+        Self { date, description, total_duration_sec, callback: action, deactivation_callback, is_active: true, is_enabled: true, was_in_period: false, is_missed: false }
+    }
+
+    pub fn description(&self) -> ArrayString<16> {
+        self.description
+    }
+
+    pub fn set_description(&mut self, description: ArrayString<16>) {
+        self.description = description;
+    }
+
+    pub fn date(&self) -> &DateFormat {
+        &self.date
+    }
+
+    pub fn set_date(&mut self, date: DateFormat) {
+        self.date = date;
+    }
+
+    pub fn total_duration_sec(&self) -> u32 {
+        self.total_duration_sec
+    }
+
+    pub fn set_total_duration_sec(&mut self, total_duration_sec: u32) {
+        self.total_duration_sec = total_duration_sec;
+    }
+
+    pub fn is_enabled(&self) -> bool {
+        self.is_enabled
+    }
+
+    pub fn set_enabled(&mut self, is_enabled: bool) {
+        self.is_enabled = is_enabled;
+    }
+
+    pub fn callback(&self) -> &C {
+        &self.callback
+    }
+
+    pub fn callback_mut(&mut self) -> &mut C {
+        &mut self.callback
     }
 }
 
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub struct WeeklyDate {
     day_of_week: DayOfWeek,
     hour: u32,
@@ -33,15 +78,61 @@ impl WeeklyDate {
     pub fn new(day_of_week: DayOfWeek, hour: u32, minute: u32, second: u32) -> Self {
         Self { day_of_week, hour, minute, second }
     }
+
+    pub fn day_of_week(&self) -> DayOfWeek {
+        self.day_of_week
+    }
+
+    pub fn hour(&self) -> u32 {
+        self.hour
+    }
+
+    pub fn minute(&self) -> u32 {
+        self.minute
+    }
+
+    pub fn second(&self) -> u32 {
+        self.second
+    }
+}
+
+/// Failure raised while triggering an alarm, tagged with the callback it came from.
+#[derive(Debug, PartialEq)]
+pub enum AlarmError<C, D> {
+    Callback(C),
+    DeactivationCallback(D),
 }
 
 pub trait Triggerable{
-    fn trigger(&mut self, current_time: &DateTime) -> bool;
+    type Error;
+    // Returns Ok(true) if the alarm has been triggered
+    fn trigger(&mut self, current_time: &DateTime) -> Result<bool, Self::Error>;
 }
 
 pub trait Armable{
     // Returns true if the alarm has been rearmed
     fn rearm(&mut self, current_time: &DateTime) -> bool;
+    // Deactivates the alarm until it gets rearmed
+    fn disarm(&mut self);
+}
+
+pub trait Schedulable{
+    // Seconds until the next activation period starts, 0 during one, None if it never will
+    fn seconds_until_next(&self, current_time: &DateTime) -> Option<u32>;
+    // Returns true while the alarm is in its activation period and has not been stopped
+    fn is_ringing(&self, current_time: &DateTime) -> bool;
+    // Returns true if the last activation period ended without the alarm being stopped
+    fn is_missed(&self) -> bool;
+    fn clear_missed(&mut self);
+}
+
+const SECONDS_IN_A_WEEK: u32 = 7 * 24 * 60 * 60;
+
+fn seconds_since_week_start(current_datetime: &DateTime) -> u32 {
+    current_datetime.second as u32
+        + current_datetime.minute as u32 * 60
+        + current_datetime.hour as u32 * 60 * 60
+        + current_datetime.day_of_week as u32 * 24 * 60 * 60
 }
 
 impl <C:Callback, D:Callback>Armable for Alarm <C, D, WeeklyDate>{
@@ -49,55 +140,89 @@ impl <C:Callback, D:Callback>Armable for Alarm <C, D, WeeklyDate>{
         let mut res = false;
         let is_in_period = self.is_date_in_activation_period(current_time);
         if !is_in_period {
+            // Still active at the end of its period: nobody stopped it
+            if self.was_in_period && self.is_active && self.is_enabled {
+                self.is_missed = true;
+            }
             self.is_active = true;
             res = true;
         }
-        return res;
+        self.was_in_period = is_in_period;
+        res
+    }
+
+    fn disarm(&mut self) {
+        self.is_active = false;
     }
 }
 
 impl <C:Callback, D:Callback>Triggerable for Alarm <C, D, WeeklyDate>{
-    fn trigger(&mut self, current_time: &DateTime) -> bool{
+    type Error = AlarmError<C::Error, D::Error>;
+
+    fn trigger(&mut self, current_time: &DateTime) -> Result<bool, Self::Error>{
         let mut triggered = false;
-        if self.is_active && self.is_date_in_activation_period(current_time) {
-            self.is_active = self.callback.call();
+        if self.is_enabled && self.is_active && self.is_date_in_activation_period(current_time) {
+            // On error the alarm stays active, so the next trigger retries it.
+            self.is_active = self.callback.call().map_err(AlarmError::Callback)?;
             if !self.is_active {//If callback has been stopped...
-                self.deactivation_callback.call();//...call the deactivation callback.
+                self.is_missed = false;
+                self.deactivation_callback.call().map_err(AlarmError::DeactivationCallback)?;//...call the deactivation callback.
             }
             triggered = true;
         }
-        return triggered
+        Ok(triggered)
+    }
+}
+
+impl <C:Callback, D:Callback>Schedulable for Alarm <C, D, WeeklyDate>{
+    fn seconds_until_next(&self, current_time: &DateTime) -> Option<u32> {
+        if !self.is_enabled {
+            return None;
+        }
+        if self.is_date_in_activation_period(current_time) {
+            return Some(0);
+        }
+        let now = seconds_since_week_start(current_time);
+        Some((self.trigger_time_in_seconds() + SECONDS_IN_A_WEEK - now) % SECONDS_IN_A_WEEK)
+    }
+
+    fn is_ringing(&self, current_time: &DateTime) -> bool {
+        self.is_enabled && self.is_active && self.is_date_in_activation_period(current_time)
+    }
+
+    fn is_missed(&self) -> bool {
+        self.is_missed
+    }
+
+    fn clear_missed(&mut self) {
+        self.is_missed = false;
     }
 }
 
 impl <C:Callback, D:Callback> Alarm <C, D, WeeklyDate>{
-    pub fn is_date_in_activation_period(&self, current_datetime: &DateTime) -> bool {
-        let mut seconds_since_week_start = 0u32;
-        seconds_since_week_start += current_datetime.second as u32;
-        seconds_since_week_start += current_datetime.minute as u32 * 60;
-        seconds_since_week_start += current_datetime.hour as u32 * 60 * 60;
-        seconds_since_week_start += current_datetime.day_of_week as u32 * 24 * 60 * 60;
+    fn trigger_time_in_seconds(&self) -> u32 {
+        self.date.second
+            + self.date.minute * 60
+            + self.date.hour * 60 * 60
+            + self.date.day_of_week as u32 * 24 * 60 * 60
+    }
 
-        let mut trigger_time_in_seconds = 0u32;
-        trigger_time_in_seconds += self.date.second as u32;
-        trigger_time_in_seconds += self.date.minute as u32 * 60;
-        trigger_time_in_seconds += self.date.hour as u32 * 60 * 60;
-        trigger_time_in_seconds += self.date.day_of_week as u32 * 24 * 60 * 60;
+    pub fn is_date_in_activation_period(&self, current_datetime: &DateTime) -> bool {
+        let seconds_since_week_start = seconds_since_week_start(current_datetime);
+        let trigger_time_in_seconds = self.trigger_time_in_seconds();
 
-        let number_of_seconds_in_a_week = 7 * 24 * 60 * 60;
+        let number_of_seconds_in_a_week = SECONDS_IN_A_WEEK;
 
         let end_time_in_seconds = trigger_time_in_seconds + self.total_duration_sec;
 
-        let is_in_activation_period = if end_time_in_seconds >= trigger_time_in_seconds {
+        if end_time_in_seconds >= trigger_time_in_seconds {
             // Case start < end%second_in_week
             seconds_since_week_start >= trigger_time_in_seconds
                 && seconds_since_week_start <= end_time_in_seconds
         } else {
             seconds_since_week_start > trigger_time_in_seconds
                 || seconds_since_week_start < end_time_in_seconds % number_of_seconds_in_a_week
-        };
-
-        return is_in_activation_period;
+        }
     }
 }
 
@@ -105,27 +230,19 @@ impl <C:Callback, D:Callback> Alarm <C, D, WeeklyDate>{
 mod tests {
     use arrayvec::ArrayString;
     use rp_pico::hal::rtc::DayOfWeek;
-    use callback::Callback;
-    use crate::{Alarm, DateTime, Triggerable, WeeklyDate, Armable};
-
-    struct DummyCallback {}
-    impl Callback for DummyCallback {
-        fn call(&mut self) -> bool {
-            println!("SQUIK :3");
-            return true
-        }
-    }
+    use callback_mock::{assert_called_at, assert_called_before, assert_called_once, assert_called_times, assert_not_called, MockClock, RecordingCallback, ScriptedCallback};
+    use crate::{Alarm, AlarmError, DateTime, Triggerable, WeeklyDate, Armable, Schedulable};
 
     #[test]
     fn simple_in_period_date_check() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
             callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -137,19 +254,19 @@ mod tests {
             second: 20
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, true);
+        assert!(res);
     }
 
     #[test]
     fn simple_not_in_period_date_check() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -161,19 +278,19 @@ mod tests {
             second: 0
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, false);
+        assert!(!res);
     }
 
     #[test]
     fn simple_start_of_period() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -185,19 +302,19 @@ mod tests {
             second: 10
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, true);
+        assert!(res);
     }
 
     #[test]
     fn simple_end_of_period() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -209,19 +326,19 @@ mod tests {
             second: 40
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, true);
+        assert!(res);
     }
 
     #[test]
     fn simple_1sec_after_end_of_period() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -233,19 +350,19 @@ mod tests {
             second: 41
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, false);
+        assert!(!res);
     }
 
     #[test]
     fn simple_complicated_case() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Sunday,
             23,
             59,
-            59), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            59), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -257,19 +374,19 @@ mod tests {
             second: 10
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, true);
+        assert!(res);
     }
 
     #[test]
     fn simple_complicated_case_false() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Sunday,
             23,
             59,
-            59), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            59), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -281,19 +398,19 @@ mod tests {
             second: 41
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, false);
+        assert!(!res);
     }
 
     #[test]
     fn simple_complicated_case_true_start_limit() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Sunday,
             23,
             59,
-            59), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            59), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -305,19 +422,19 @@ mod tests {
             second: 59
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, true);
+        assert!(res);
     }
 
     #[test]
     fn simple_complicated_case_true_end_limit() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Sunday,
             23,
             59,
-            59), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            59), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -329,19 +446,19 @@ mod tests {
             second: 29
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, true);
+        assert!(res);
     }
 
     #[test]
     fn simple_complicated_case_false_end_limit() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Sunday,
             23,
             59,
-            59), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            59), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -353,19 +470,19 @@ mod tests {
             second: 30
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, false);
+        assert!(!res);
     }
 
     #[test]
     fn simple_complicated_case_false_start_limit() {
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Sunday,
             23,
             59,
-            59), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            59), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -377,19 +494,19 @@ mod tests {
             second: 58
         };
         let res = alarm.is_date_in_activation_period(&time);
-        assert_eq!(res, false);
+        assert!(!res);
     }
 
     #[test]
     fn triggr_test_true(){
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let mut alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -401,19 +518,19 @@ mod tests {
             second: 20
         };
 
-        assert_eq!(alarm.trigger(&time),true);
+        assert_eq!(alarm.trigger(&time), Ok(true));
     }
 
     #[test]
     fn triggr_test_false(){
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let mut alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                    callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -425,19 +542,19 @@ mod tests {
             second: 0
         };
 
-        assert_eq!(alarm.trigger(&time),false);
+        assert_eq!(alarm.trigger(&time), Ok(false));
     }
 
     #[test]
     fn rearm_before_period(){
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let mut alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                    callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -460,21 +577,21 @@ mod tests {
         };
 
         alarm.is_active = false;
-        assert_eq!(alarm.rearm(&time), true);
-        assert_eq!(alarm.is_active, true);
-        assert_eq!(alarm.trigger(&time_bis), false);
+        assert!(alarm.rearm(&time));
+        assert!(alarm.is_active);
+        assert_eq!(alarm.trigger(&time_bis), Ok(false));
     }
 
     #[test]
     fn rearm_during_period(){
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let mut alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                    callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -497,20 +614,20 @@ mod tests {
         }; 
 
         alarm.is_active = false; // We simulate an abortion
-        assert_eq!(alarm.rearm(&time), false);
-        assert_eq!(alarm.trigger(&time_bis), false);
+        assert!(!alarm.rearm(&time));
+        assert_eq!(alarm.trigger(&time_bis), Ok(false));
     }
 
     #[test]
     fn rearm_after_period(){
-        let callback1 = DummyCallback{};
-        let callback2 = DummyCallback{};
+        let callback1 = RecordingCallback::new(MockClock::new());
+        let callback2 = RecordingCallback::new(MockClock::new());
 
         let mut alarm = Alarm::new(WeeklyDate::new(
             DayOfWeek::Monday,
             0,
             0,
-            10), ArrayString::<16>::from("descr").unwrap(), 30, 0, 0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
                                    callback1, callback2);
         let time = DateTime{
             year: 0,
@@ -533,7 +650,175 @@ mod tests {
         }; 
 
         alarm.is_active = false; // We simulate an abortion
-        assert_eq!(alarm.rearm(&time), true); 
-        assert_eq!(alarm.trigger(&time_bis), false);
+        assert!(alarm.rearm(&time)); 
+        assert_eq!(alarm.trigger(&time_bis), Ok(false));
+    }
+
+    fn monday_alarm<C, D>(callback: C, deactivation_callback: D) -> Alarm<C, D, WeeklyDate> {
+        Alarm::new(WeeklyDate::new(
+            DayOfWeek::Monday,
+            0,
+            0,
+            10), ArrayString::<16>::from("descr").unwrap(), 30,
+                   callback, deactivation_callback)
+    }
+
+    fn monday_at(second: u8) -> DateTime {
+        DateTime{
+            year: 0,
+            month: 0,
+            day: 0,
+            day_of_week: DayOfWeek::Monday,
+            hour: 0,
+            minute: 0,
+            second
+        }
+    }
+
+    #[test]
+    fn trigger_callback_error_keeps_alarm_active(){
+        let clock = MockClock::new();
+        let deactivation_callback = RecordingCallback::new(clock.clone());
+        let deactivation_log = deactivation_callback.log();
+        let mut alarm = monday_alarm(
+            ScriptedCallback::new(clock.clone(), vec![Err("I2C NACK"), Ok(true)]),
+            deactivation_callback);
+
+        assert_eq!(alarm.trigger(&monday_at(20)), Err(AlarmError::Callback("I2C NACK")));
+        assert!(alarm.is_active);
+        assert_eq!(alarm.trigger(&monday_at(21)), Ok(true));
+        assert_not_called(&deactivation_log);
+    }
+
+    #[test]
+    fn trigger_deactivation_callback_error_still_deactivates(){
+        let clock = MockClock::new();
+        let mut alarm = monday_alarm(
+            RecordingCallback::stopping(clock.clone()),
+            ScriptedCallback::new(clock.clone(), vec![Err("I2C NACK")]));
+
+        assert_eq!(alarm.trigger(&monday_at(20)), Err(AlarmError::DeactivationCallback("I2C NACK")));
+        assert!(!alarm.is_active);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn deactivation_callback_not_called_while_ringing(){
+        let clock = MockClock::new();
+        let callback = RecordingCallback::new(clock.clone());
+        let callback_log = callback.log();
+        let deactivation_callback = RecordingCallback::new(clock.clone());
+        let deactivation_log = deactivation_callback.log();
+        let mut alarm = monday_alarm(callback, deactivation_callback);
+
+        for second in 10..15 {
+            clock.advance_ms(1000);
+            assert_eq!(alarm.trigger(&monday_at(second)), Ok(true));
+        }
+
+        assert_called_at(&callback_log, &[1000, 2000, 3000, 4000, 5000]);
+        assert_not_called(&deactivation_log);
+    }
+
+    #[test]
+    fn deactivation_callback_called_once_when_stopped(){
+        let clock = MockClock::new();
+        let callback = ScriptedCallback::<()>::new(clock.clone(), vec![Ok(true), Ok(true), Ok(false)]);
+        let callback_log = callback.log();
+        let deactivation_callback = RecordingCallback::new(clock.clone());
+        let deactivation_log = deactivation_callback.log();
+        let mut alarm = monday_alarm(callback, deactivation_callback);
+
+        for second in 10..15 {
+            clock.advance_ms(1000);
+            alarm.trigger(&monday_at(second)).unwrap();
+        }
+
+        // The alarm stops ringing once the callback has been stopped.
+        assert_called_times(&callback_log, 3);
+        assert_called_once(&deactivation_log);
+        assert_called_at(&deactivation_log, &[3000]);
+        assert_called_before(&callback_log, &deactivation_log);
+    }
+
+    #[test]
+    fn deactivation_callback_not_called_outside_period(){
+        let clock = MockClock::new();
+        let callback = RecordingCallback::stopping(clock.clone());
+        let callback_log = callback.log();
+        let deactivation_callback = RecordingCallback::new(clock.clone());
+        let deactivation_log = deactivation_callback.log();
+        let mut alarm = monday_alarm(callback, deactivation_callback);
+
+        assert_eq!(alarm.trigger(&monday_at(0)), Ok(false));
+        assert_not_called(&callback_log);
+        assert_not_called(&deactivation_log);
+    }
+
+    #[test]
+    fn disabled_alarm_is_not_triggered(){
+        let clock = MockClock::new();
+        let callback = RecordingCallback::new(clock.clone());
+        let callback_log = callback.log();
+        let mut alarm = monday_alarm(callback, RecordingCallback::new(clock.clone()));
+
+        alarm.set_enabled(false);
+        assert_eq!(alarm.trigger(&monday_at(20)), Ok(false));
+        assert_not_called(&callback_log);
+
+        alarm.set_enabled(true);
+        assert_eq!(alarm.trigger(&monday_at(21)), Ok(true));
+        assert_called_once(&callback_log);
+    }
+
+    #[test]
+    fn edited_date_moves_the_activation_period(){
+        let clock = MockClock::new();
+        let mut alarm = monday_alarm(RecordingCallback::new(clock.clone()), RecordingCallback::new(clock));
+
+        alarm.set_date(WeeklyDate::new(DayOfWeek::Monday, 0, 0, 40));
+        alarm.set_total_duration_sec(5);
+
+        assert_eq!(alarm.date().second(), 40);
+        assert!(!alarm.is_date_in_activation_period(&monday_at(20)));
+        assert!(alarm.is_date_in_activation_period(&monday_at(45)));
+    }
+
+    #[test]
+    fn seconds_until_next_wraps_around_the_week(){
+        let clock = MockClock::new();
+        let mut alarm = monday_alarm(RecordingCallback::new(clock.clone()), RecordingCallback::new(clock));
+
+        assert_eq!(alarm.seconds_until_next(&monday_at(0)), Some(10));
+        assert_eq!(alarm.seconds_until_next(&monday_at(20)), Some(0));
+        assert_eq!(alarm.seconds_until_next(&monday_at(50)), Some(7 * 24 * 3600 - 40));
+
+        alarm.set_enabled(false);
+        assert_eq!(alarm.seconds_until_next(&monday_at(0)), None);
+    }
+
+    #[test]
+    fn unstopped_alarm_is_missed_at_the_end_of_its_period(){
+        let clock = MockClock::new();
+        let mut alarm = monday_alarm(RecordingCallback::new(clock.clone()), RecordingCallback::new(clock));
+
+        alarm.rearm(&monday_at(20));
+        assert_eq!(alarm.trigger(&monday_at(20)), Ok(true));
+        assert!(!alarm.is_missed());
+        alarm.rearm(&monday_at(50));
+        assert!(alarm.is_missed());
+
+        alarm.clear_missed();
+        assert!(!alarm.is_missed());
+    }
+
+    #[test]
+    fn stopped_alarm_is_not_missed(){
+        let clock = MockClock::new();
+        let mut alarm = monday_alarm(RecordingCallback::stopping(clock.clone()), RecordingCallback::new(clock));
+
+        alarm.rearm(&monday_at(20));
+        alarm.trigger(&monday_at(20)).unwrap();
+        alarm.rearm(&monday_at(50));
+        assert!(!alarm.is_missed());
+    }
+}
@@ -0,0 +1,195 @@
+#![cfg_attr(not(test), no_std)]
+
+pub mod melodies;
+pub mod pwm;
+pub mod rtttl;
+
+use embedded_hal::blocking::delay::DelayMs;
+
+/// Silence left between two notes so that repeated notes can be told apart.
+pub const NOTE_GAP_MS: u16 = 10;
+
+/// Piezo buzzer able to play square-wave tones.
+pub trait Buzzer {
+    type Error;
+
+    fn start_tone(&mut self, frequency_hz: u16) -> Result<(), Self::Error>;
+    fn stop(&mut self) -> Result<(), Self::Error>;
+}
+
+/// The twelve pitches of an octave, starting from C.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pitch {
+    C = 0,
+    CSharp = 1,
+    D = 2,
+    DSharp = 3,
+    E = 4,
+    F = 5,
+    FSharp = 6,
+    G = 7,
+    GSharp = 8,
+    A = 9,
+    ASharp = 10,
+    B = 11,
+}
+
+// Frequencies of the 8th octave, lower octaves are obtained by halving them.
+const OCTAVE_8_FREQUENCIES_HZ: [u16; 12] = [
+    4186, 4435, 4699, 4978, 5274, 5588, 5920, 6272, 6645, 7040, 7459, 7902,
+];
+
+pub const MAX_OCTAVE: u8 = 8;
+
+impl Pitch {
+    /// Frequency of the pitch in the given octave, A4 being 440 Hz.
+    pub const fn frequency_hz(self, octave: u8) -> u16 {
+        let octave = if octave > MAX_OCTAVE {
+            MAX_OCTAVE
+        } else {
+            octave
+        };
+        OCTAVE_8_FREQUENCIES_HZ[self as usize] >> (MAX_OCTAVE - octave)
+    }
+}
+
+/// A tone, or a rest when the frequency is 0.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Note {
+    pub frequency_hz: u16,
+    pub duration_ms: u16,
+}
+
+impl Note {
+    pub const fn new(frequency_hz: u16, duration_ms: u16) -> Self {
+        Self {
+            frequency_hz,
+            duration_ms,
+        }
+    }
+
+    pub const fn pitch(pitch: Pitch, octave: u8, duration_ms: u16) -> Self {
+        Self::new(pitch.frequency_hz(octave), duration_ms)
+    }
+
+    pub const fn rest(duration_ms: u16) -> Self {
+        Self::new(0, duration_ms)
+    }
+
+    pub fn is_rest(&self) -> bool {
+        self.frequency_hz == 0
+    }
+}
+
+/// Plays the notes one after the other and leaves the buzzer silent.
+pub fn play_melody<B, DL, I>(buzzer: &mut B, delay: &mut DL, notes: I) -> Result<(), B::Error>
+where
+    B: Buzzer,
+    DL: DelayMs<u32>,
+    I: IntoIterator<Item = Note>,
+{
+    for note in notes {
+        if note.is_rest() {
+            buzzer.stop()?;
+            delay.delay_ms(note.duration_ms as u32);
+        } else {
+            let gap_ms = NOTE_GAP_MS.min(note.duration_ms);
+            buzzer.start_tone(note.frequency_hz)?;
+            delay.delay_ms((note.duration_ms - gap_ms) as u32);
+            buzzer.stop()?;
+            delay.delay_ms(gap_ms as u32);
+        }
+    }
+    buzzer.stop()
+}
+
+#[cfg(test)]
+mod tests {
+    use core::cell::RefCell;
+    use core::convert::Infallible;
+    use embedded_hal::blocking::delay::DelayMs;
+    use std::rc::Rc;
+
+    use crate::{play_melody, Buzzer, Note, Pitch};
+
+    #[derive(Debug, PartialEq)]
+    enum Event {
+        Tone(u16),
+        Stop,
+        Wait(u32),
+    }
+
+    struct FakeBuzzer {
+        events: Rc<RefCell<Vec<Event>>>,
+    }
+
+    impl Buzzer for FakeBuzzer {
+        type Error = Infallible;
+
+        fn start_tone(&mut self, frequency_hz: u16) -> Result<(), Self::Error> {
+            self.events.borrow_mut().push(Event::Tone(frequency_hz));
+            Ok(())
+        }
+
+        fn stop(&mut self) -> Result<(), Self::Error> {
+            self.events.borrow_mut().push(Event::Stop);
+            Ok(())
+        }
+    }
+
+    struct FakeDelay {
+        events: Rc<RefCell<Vec<Event>>>,
+    }
+
+    impl DelayMs<u32> for FakeDelay {
+        fn delay_ms(&mut self, ms: u32) {
+            self.events.borrow_mut().push(Event::Wait(ms));
+        }
+    }
+
+    #[test]
+    fn pitch_frequencies() {
+        assert_eq!(Pitch::A.frequency_hz(4), 440);
+        assert_eq!(Pitch::A.frequency_hz(5), 880);
+        assert_eq!(Pitch::C.frequency_hz(4), 261);
+        assert_eq!(Pitch::B.frequency_hz(8), 7902);
+        // Octaves above the 8th are clamped
+        assert_eq!(Pitch::B.frequency_hz(9), 7902);
+    }
+
+    #[test]
+    fn play_melody_with_rest() {
+        let events = Rc::new(RefCell::new(Vec::new()));
+        let mut buzzer = FakeBuzzer {
+            events: Rc::clone(&events),
+        };
+        let mut delay = FakeDelay {
+            events: Rc::clone(&events),
+        };
+        let melody = [
+            Note::pitch(Pitch::E, 5, 150),
+            Note::rest(100),
+            Note::new(440, 5),
+        ];
+
+        play_melody(&mut buzzer, &mut delay, melody).unwrap();
+
+        assert_eq!(
+            *events.borrow(),
+            vec![
+                Event::Tone(659),
+                Event::Wait(140),
+                Event::Stop,
+                Event::Wait(10),
+                Event::Stop,
+                Event::Wait(100),
+                // Notes shorter than the gap are only silence
+                Event::Tone(440),
+                Event::Wait(0),
+                Event::Stop,
+                Event::Wait(5),
+                Event::Stop,
+            ]
+        );
+    }
+}
@@ -0,0 +1,21 @@
+//! Alarm melodies as RTTTL ringtones, so that each bin can be told apart by ear.
+
+/// Rising arpeggio for the green bin.
+pub const GREEN_BIN: &str = "Verte:d=8,o=5,b=200:c,e,g,4c6";
+
+/// Two-tone chime for the yellow bin.
+pub const YELLOW_BIN: &str = "Jaune:d=4,o=5,b=240:a,f,a,f";
+
+#[cfg(test)]
+mod tests {
+    use crate::melodies::{GREEN_BIN, YELLOW_BIN};
+    use crate::rtttl::Rtttl;
+
+    #[test]
+    fn melodies_parse() {
+        for melody in [GREEN_BIN, YELLOW_BIN] {
+            let rtttl = Rtttl::parse(melody).unwrap();
+            assert!(rtttl.notes().count() > 0);
+        }
+    }
+}
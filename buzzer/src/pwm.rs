@@ -0,0 +1,101 @@
+//! Clock divider computation for the RP2040 PWM slices.
+//!
+//! A slice counts from 0 to `top` at `clock / divider`, so its output
+//! frequency is `clock / (divider * (top + 1))`. The divider is a fixed point
+//! number with 8 integer bits and 4 fractional bits.
+
+/// Slice configuration producing a given output frequency.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PwmConfig {
+    pub div_int: u8,
+    pub div_frac: u8,
+    pub top: u16,
+}
+
+impl PwmConfig {
+    /// Compare value giving a 50% duty cycle, the loudest setting for a piezo.
+    pub fn half_duty(&self) -> u16 {
+        (self.top as u32).div_ceil(2) as u16
+    }
+}
+
+const MIN_DIVIDER_SIXTEENTHS: u64 = 16;
+const MAX_DIVIDER_SIXTEENTHS: u64 = 255 * 16 + 15;
+const COUNTER_RANGE: u64 = u16::MAX as u64 + 1;
+
+/// Returns the configuration closest to `frequency_hz`, or `None` when the
+/// frequency cannot be reached from `clock_hz`.
+///
+/// The smallest divider is chosen to keep the best resolution on `top`.
+pub fn pwm_config(clock_hz: u32, frequency_hz: u32) -> Option<PwmConfig> {
+    if frequency_hz == 0 {
+        return None;
+    }
+    let clock_sixteenths = clock_hz as u64 * 16;
+    let frequency_hz = frequency_hz as u64;
+
+    // Smallest divider for which `top + 1` fits in the 16 bit counter.
+    let divider = clock_sixteenths
+        .div_ceil(frequency_hz * COUNTER_RANGE)
+        .max(MIN_DIVIDER_SIXTEENTHS);
+    if divider > MAX_DIVIDER_SIXTEENTHS {
+        return None;
+    }
+
+    let period = (clock_sixteenths + divider * frequency_hz / 2) / (divider * frequency_hz);
+    if period < 2 {
+        return None;
+    }
+
+    Some(PwmConfig {
+        div_int: (divider / 16) as u8,
+        div_frac: (divider % 16) as u8,
+        top: (period.min(COUNTER_RANGE) - 1) as u16,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::pwm::{pwm_config, PwmConfig};
+
+    const CLOCK_HZ: u32 = 125_000_000;
+
+    fn output_frequency(config: &PwmConfig) -> f64 {
+        let divider = config.div_int as f64 + config.div_frac as f64 / 16.;
+        CLOCK_HZ as f64 / (divider * (config.top as f64 + 1.))
+    }
+
+    #[test]
+    fn a4_is_within_a_tenth_of_hertz() {
+        let config = pwm_config(CLOCK_HZ, 440).unwrap();
+
+        assert_eq!(
+            config,
+            PwmConfig {
+                div_int: 4,
+                div_frac: 6,
+                top: 64_934
+            }
+        );
+        assert!((output_frequency(&config) - 440.).abs() < 0.1);
+    }
+
+    #[test]
+    fn high_frequencies_need_no_divider() {
+        let config = pwm_config(CLOCK_HZ, 4000).unwrap();
+
+        assert_eq!(config.div_int, 1);
+        assert_eq!(config.div_frac, 0);
+        assert_eq!(config.top, 31_249);
+        assert_eq!(config.half_duty(), 15_625);
+    }
+
+    #[test]
+    fn unreachable_frequencies() {
+        assert_eq!(pwm_config(CLOCK_HZ, 0), None);
+        // Below clock / (256 * 65536)
+        assert_eq!(pwm_config(CLOCK_HZ, 7), None);
+        // The counter needs at least two steps
+        assert_eq!(pwm_config(CLOCK_HZ, CLOCK_HZ), None);
+    }
+}
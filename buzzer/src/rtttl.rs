@@ -0,0 +1,327 @@
+//! Parser for RTTTL ringtones, e.g. `Bins:d=4,o=5,b=140:8c,8e,g,2c6`.
+//!
+//! A ringtone is made of a name, default settings (`d` duration, `o` octave,
+//! `b` tempo in beats per minute) and a comma separated list of notes. Each
+//! note is `[duration]letter[#][.][octave][.]`, `p` being a pause.
+
+use crate::{Note, Pitch, MAX_OCTAVE};
+
+const DEFAULT_DURATION: u16 = 4;
+const DEFAULT_OCTAVE: u8 = 6;
+const DEFAULT_TEMPO: u16 = 63;
+const MIN_TEMPO: u16 = 25;
+const MAX_TEMPO: u16 = 900;
+
+#[derive(Debug, PartialEq)]
+pub enum RtttlError {
+    /// The string is not made of `name:settings:notes`.
+    MissingSection,
+    /// A setting is not `d=`, `o=` or `b=` followed by a number.
+    InvalidSetting,
+    /// Durations must be 1, 2, 4, 8, 16 or 32.
+    InvalidDuration(u16),
+    /// Octaves must be between 0 and 8.
+    InvalidOctave(u8),
+    /// Tempos must be between 25 and 900 beats per minute.
+    InvalidTempo(u16),
+    /// The note at this index of the note list is malformed.
+    InvalidNote(usize),
+    /// The note list is empty.
+    NoNotes,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Settings {
+    duration: u16,
+    octave: u8,
+    tempo: u16,
+}
+
+/// A validated RTTTL ringtone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Rtttl<'a> {
+    name: &'a str,
+    settings: Settings,
+    notes: &'a str,
+}
+
+impl<'a> Rtttl<'a> {
+    /// Parses the ringtone and checks every note, so that `notes` cannot fail.
+    pub fn parse(ringtone: &'a str) -> Result<Self, RtttlError> {
+        let mut sections = ringtone.splitn(3, ':');
+        let name = sections.next().ok_or(RtttlError::MissingSection)?;
+        let settings = sections.next().ok_or(RtttlError::MissingSection)?;
+        let notes = sections.next().ok_or(RtttlError::MissingSection)?;
+
+        let rtttl = Self {
+            name: name.trim(),
+            settings: parse_settings(settings)?,
+            notes,
+        };
+        if notes.trim().is_empty() {
+            return Err(RtttlError::NoNotes);
+        }
+        for (index, note) in notes.split(',').enumerate() {
+            parse_note(note, &rtttl.settings, index)?;
+        }
+        Ok(rtttl)
+    }
+
+    pub fn name(&self) -> &'a str {
+        self.name
+    }
+
+    pub fn tempo(&self) -> u16 {
+        self.settings.tempo
+    }
+
+    pub fn notes(&self) -> Notes<'a> {
+        Notes {
+            settings: self.settings,
+            notes: self.notes.split(','),
+            index: 0,
+        }
+    }
+}
+
+/// Iterator over the notes of a ringtone.
+pub struct Notes<'a> {
+    settings: Settings,
+    notes: core::str::Split<'a, char>,
+    index: usize,
+}
+
+impl Iterator for Notes<'_> {
+    type Item = Note;
+
+    fn next(&mut self) -> Option<Note> {
+        let note = self.notes.next()?;
+        self.index += 1;
+        // Every note has been checked by `Rtttl::parse`.
+        parse_note(note, &self.settings, self.index - 1).ok()
+    }
+}
+
+fn parse_settings(settings: &str) -> Result<Settings, RtttlError> {
+    let mut parsed = Settings {
+        duration: DEFAULT_DURATION,
+        octave: DEFAULT_OCTAVE,
+        tempo: DEFAULT_TEMPO,
+    };
+    for setting in settings.split(',').map(str::trim).filter(|s| !s.is_empty()) {
+        let (key, value) = setting.split_once('=').ok_or(RtttlError::InvalidSetting)?;
+        let value: u16 = value
+            .trim()
+            .parse()
+            .map_err(|_| RtttlError::InvalidSetting)?;
+        match key.trim() {
+            "d" => parsed.duration = check_duration(value)?,
+            "o" => parsed.octave = check_octave(value)?,
+            "b" => parsed.tempo = check_tempo(value)?,
+            _ => return Err(RtttlError::InvalidSetting),
+        }
+    }
+    Ok(parsed)
+}
+
+fn check_duration(duration: u16) -> Result<u16, RtttlError> {
+    match duration {
+        1 | 2 | 4 | 8 | 16 | 32 => Ok(duration),
+        _ => Err(RtttlError::InvalidDuration(duration)),
+    }
+}
+
+fn check_octave(octave: u16) -> Result<u8, RtttlError> {
+    if octave <= MAX_OCTAVE as u16 {
+        Ok(octave as u8)
+    } else {
+        Err(RtttlError::InvalidOctave(octave.min(u8::MAX as u16) as u8))
+    }
+}
+
+fn check_tempo(tempo: u16) -> Result<u16, RtttlError> {
+    if (MIN_TEMPO..=MAX_TEMPO).contains(&tempo) {
+        Ok(tempo)
+    } else {
+        Err(RtttlError::InvalidTempo(tempo))
+    }
+}
+
+// Splits the leading ASCII digits off `s`.
+fn split_number(s: &str) -> (Option<u16>, &str) {
+    let end = s.bytes().take_while(u8::is_ascii_digit).count();
+    if end == 0 {
+        (None, s)
+    } else {
+        (s[..end].parse().ok(), &s[end..])
+    }
+}
+
+fn parse_note(note: &str, settings: &Settings, index: usize) -> Result<Note, RtttlError> {
+    let invalid = RtttlError::InvalidNote(index);
+    let note = note.trim();
+
+    let (duration, rest) = split_number(note);
+    let duration = match duration {
+        Some(duration) => check_duration(duration)?,
+        None if rest.len() == note.len() => settings.duration,
+        None => return Err(invalid),
+    };
+
+    let mut chars = rest.chars();
+    let letter = chars.next().ok_or(RtttlError::InvalidNote(index))?;
+    let mut rest = chars.as_str();
+    let sharp = rest.starts_with('#');
+    if sharp {
+        rest = &rest[1..];
+    }
+    let mut dotted = rest.starts_with('.');
+    if dotted {
+        rest = &rest[1..];
+    }
+    let (octave, after_octave) = split_number(rest);
+    let octave = match octave {
+        Some(octave) => check_octave(octave)?,
+        None if after_octave.len() == rest.len() => settings.octave,
+        None => return Err(invalid),
+    };
+    let mut rest = after_octave;
+    if !dotted && rest.starts_with('.') {
+        dotted = true;
+        rest = &rest[1..];
+    }
+    if !rest.is_empty() {
+        return Err(invalid);
+    }
+
+    let pitch = match (letter.to_ascii_lowercase(), sharp) {
+        ('p', false) => None,
+        ('c', false) => Some(Pitch::C),
+        ('c', true) => Some(Pitch::CSharp),
+        ('d', false) => Some(Pitch::D),
+        ('d', true) => Some(Pitch::DSharp),
+        ('e', false) => Some(Pitch::E),
+        ('f', false) => Some(Pitch::F),
+        ('f', true) => Some(Pitch::FSharp),
+        ('g', false) => Some(Pitch::G),
+        ('g', true) => Some(Pitch::GSharp),
+        ('a', false) => Some(Pitch::A),
+        ('a', true) => Some(Pitch::ASharp),
+        // `h` is the German name of B
+        ('b' | 'h', false) => Some(Pitch::B),
+        _ => return Err(invalid),
+    };
+
+    // A beat is a quarter note.
+    let whole_note_ms = 4 * 60_000 / settings.tempo as u32;
+    let mut duration_ms = whole_note_ms / duration as u32;
+    if dotted {
+        duration_ms += duration_ms / 2;
+    }
+    let duration_ms = duration_ms as u16;
+
+    Ok(match pitch {
+        Some(pitch) => Note::pitch(pitch, octave, duration_ms),
+        None => Note::rest(duration_ms),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::rtttl::{Rtttl, RtttlError};
+    use crate::{Note, Pitch};
+
+    fn notes(ringtone: &str) -> Vec<Note> {
+        Rtttl::parse(ringtone).unwrap().notes().collect()
+    }
+
+    #[test]
+    fn defaults_apply_when_settings_are_empty() {
+        let rtttl = Rtttl::parse("Bip::a").unwrap();
+
+        assert_eq!(rtttl.name(), "Bip");
+        assert_eq!(rtttl.tempo(), 63);
+        // Quarter note at 63 bpm in octave 6
+        assert_eq!(
+            rtttl.notes().collect::<Vec<_>>(),
+            vec![Note::pitch(Pitch::A, 6, 952)]
+        );
+    }
+
+    #[test]
+    fn tempo_sets_the_beat_length() {
+        assert_eq!(
+            notes("t:d=4,o=5,b=120:c,8c,2c,1c,16c,32c")
+                .iter()
+                .map(|note| note.duration_ms)
+                .collect::<Vec<_>>(),
+            vec![500, 250, 1000, 2000, 125, 62]
+        );
+        assert_eq!(notes("t:d=4,o=5,b=60:c")[0].duration_ms, 1000);
+    }
+
+    #[test]
+    fn octave_default_and_override() {
+        assert_eq!(
+            notes("t:d=4,o=4,b=120:a,a5,a7,c#8"),
+            vec![
+                Note::new(440, 500),
+                Note::new(880, 500),
+                Note::new(3520, 500),
+                Note::new(4435, 500),
+            ]
+        );
+    }
+
+    #[test]
+    fn dotted_notes_last_half_longer() {
+        assert_eq!(
+            notes("t:d=8,o=5,b=120:e,e.,e.6,e6.,4p.")
+                .iter()
+                .map(|note| note.duration_ms)
+                .collect::<Vec<_>>(),
+            vec![250, 375, 375, 375, 750]
+        );
+    }
+
+    #[test]
+    fn sharps_pauses_and_spaces() {
+        assert_eq!(
+            notes("Verte : d=16, o=6, b=100 : d#, p, F#5, h"),
+            vec![
+                Note::pitch(Pitch::DSharp, 6, 150),
+                Note::rest(150),
+                Note::pitch(Pitch::FSharp, 5, 150),
+                Note::pitch(Pitch::B, 6, 150),
+            ]
+        );
+    }
+
+    #[test]
+    fn malformed_ringtones_are_rejected() {
+        assert_eq!(Rtttl::parse("no sections"), Err(RtttlError::MissingSection));
+        assert_eq!(Rtttl::parse("t:d=4"), Err(RtttlError::MissingSection));
+        assert_eq!(Rtttl::parse("t:d=4:"), Err(RtttlError::NoNotes));
+        assert_eq!(Rtttl::parse("t:x=4:c"), Err(RtttlError::InvalidSetting));
+        assert_eq!(Rtttl::parse("t:d=:c"), Err(RtttlError::InvalidSetting));
+        assert_eq!(Rtttl::parse("t:d=3:c"), Err(RtttlError::InvalidDuration(3)));
+        assert_eq!(Rtttl::parse("t:o=9:c"), Err(RtttlError::InvalidOctave(9)));
+        assert_eq!(
+            Rtttl::parse("t:b=1000:c"),
+            Err(RtttlError::InvalidTempo(1000))
+        );
+        assert_eq!(Rtttl::parse("t:b=10:c"), Err(RtttlError::InvalidTempo(10)));
+    }
+
+    #[test]
+    fn malformed_notes_report_their_index() {
+        assert_eq!(Rtttl::parse("t::c,x"), Err(RtttlError::InvalidNote(1)));
+        assert_eq!(Rtttl::parse("t::c,e#"), Err(RtttlError::InvalidNote(1)));
+        assert_eq!(Rtttl::parse("t::c,d,,e"), Err(RtttlError::InvalidNote(2)));
+        assert_eq!(Rtttl::parse("t::c..,d"), Err(RtttlError::InvalidNote(0)));
+        assert_eq!(Rtttl::parse("t::c5x"), Err(RtttlError::InvalidNote(0)));
+        assert_eq!(Rtttl::parse("t::12c"), Err(RtttlError::InvalidDuration(12)));
+        assert_eq!(Rtttl::parse("t::c9"), Err(RtttlError::InvalidOctave(9)));
+        assert_eq!(Rtttl::parse("t::c99999"), Err(RtttlError::InvalidNote(0)));
+    }
+}
@@ -0,0 +1,110 @@
+//! Test doubles for the `Callback` and `Stopper` traits.
+//!
+//! The doubles are moved into the code under test, so each of them shares a
+//! `CallLog` with the test through an `Rc`. Timestamps are read from a
+//! `MockClock` that the test advances by hand.
+#![cfg_attr(not(test), no_std)]
+
+extern crate alloc;
+
+pub mod recording;
+pub mod scripted;
+
+pub use recording::RecordingCallback;
+pub use scripted::{ScriptedCallback, ScriptedStopper};
+
+use alloc::rc::Rc;
+use alloc::vec::Vec;
+use core::cell::{Cell, RefCell};
+
+/// Fake monotonic clock in milliseconds, shared by cloning.
+#[derive(Clone, Default)]
+pub struct MockClock {
+    now_ms: Rc<Cell<u64>>,
+}
+
+impl MockClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn now_ms(&self) -> u64 {
+        self.now_ms.get()
+    }
+
+    pub fn set_ms(&self, now_ms: u64) {
+        self.now_ms.set(now_ms);
+    }
+
+    pub fn advance_ms(&self, ms: u64) {
+        self.now_ms.set(self.now_ms.get() + ms);
+    }
+}
+
+/// Timestamps of the calls received by a double, shared by cloning.
+#[derive(Clone, Default)]
+pub struct CallLog {
+    timestamps_ms: Rc<RefCell<Vec<u64>>>,
+}
+
+impl CallLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn record(&self, timestamp_ms: u64) {
+        self.timestamps_ms.borrow_mut().push(timestamp_ms);
+    }
+
+    pub fn call_count(&self) -> usize {
+        self.timestamps_ms.borrow().len()
+    }
+
+    pub fn timestamps_ms(&self) -> Vec<u64> {
+        self.timestamps_ms.borrow().clone()
+    }
+
+    pub fn last_call_ms(&self) -> Option<u64> {
+        self.timestamps_ms.borrow().last().copied()
+    }
+}
+
+#[track_caller]
+pub fn assert_not_called(log: &CallLog) {
+    assert_called_times(log, 0);
+}
+
+#[track_caller]
+pub fn assert_called_once(log: &CallLog) {
+    assert_called_times(log, 1);
+}
+
+#[track_caller]
+pub fn assert_called_times(log: &CallLog, times: usize) {
+    assert_eq!(log.call_count(), times, "unexpected number of calls");
+}
+
+#[track_caller]
+pub fn assert_called_at(log: &CallLog, timestamps_ms: &[u64]) {
+    assert_eq!(
+        log.timestamps_ms(),
+        timestamps_ms,
+        "unexpected call timestamps"
+    );
+}
+
+/// Asserts that every call of `first` happened before the first call of `then`.
+#[track_caller]
+pub fn assert_called_before(first: &CallLog, then: &CallLog) {
+    let first_last = first.last_call_ms().expect("first log has no calls");
+    let then_first = *then
+        .timestamps_ms()
+        .first()
+        .expect("second log has no calls");
+    assert!(
+        first_last <= then_first,
+        "last call at {} ms is after the first call at {} ms",
+        first_last,
+        then_first
+    );
+}
@@ -0,0 +1,44 @@
+use core::convert::Infallible;
+
+use callback::Callback;
+
+use crate::{CallLog, MockClock};
+
+/// Callback that records the clock value of each call and always succeeds.
+pub struct RecordingCallback {
+    clock: MockClock,
+    log: CallLog,
+    keep_going: bool,
+}
+
+impl RecordingCallback {
+    /// Records the call and asks the alarm to keep ringing.
+    pub fn new(clock: MockClock) -> Self {
+        Self {
+            clock,
+            log: CallLog::new(),
+            keep_going: true,
+        }
+    }
+
+    /// Records the call and reports that the alarm has been stopped.
+    pub fn stopping(clock: MockClock) -> Self {
+        Self {
+            keep_going: false,
+            ..Self::new(clock)
+        }
+    }
+
+    pub fn log(&self) -> CallLog {
+        self.log.clone()
+    }
+}
+
+impl Callback for RecordingCallback {
+    type Error = Infallible;
+
+    fn call(&mut self) -> Result<bool, Self::Error> {
+        self.log.record(self.clock.now_ms());
+        Ok(self.keep_going)
+    }
+}
@@ -0,0 +1,107 @@
+use alloc::collections::VecDeque;
+use alloc::vec::Vec;
+
+use callback::{Callback, Stopper};
+
+use crate::{CallLog, MockClock};
+
+/// Callback returning a predefined sequence of outcomes.
+///
+/// Panics when called more often than scripted, so an unexpected call fails
+/// the test instead of going unnoticed.
+pub struct ScriptedCallback<E> {
+    clock: MockClock,
+    log: CallLog,
+    outcomes: VecDeque<Result<bool, E>>,
+}
+
+impl<E> ScriptedCallback<E> {
+    pub fn new(clock: MockClock, outcomes: Vec<Result<bool, E>>) -> Self {
+        Self {
+            clock,
+            log: CallLog::new(),
+            outcomes: outcomes.into(),
+        }
+    }
+
+    pub fn log(&self) -> CallLog {
+        self.log.clone()
+    }
+}
+
+impl<E> Callback for ScriptedCallback<E> {
+    type Error = E;
+
+    fn call(&mut self) -> Result<bool, Self::Error> {
+        self.log.record(self.clock.now_ms());
+        self.outcomes
+            .pop_front()
+            .expect("ScriptedCallback called more times than scripted")
+    }
+}
+
+/// Stopper returning a predefined sequence of answers.
+///
+/// Panics when polled more often than scripted.
+pub struct ScriptedStopper<E> {
+    clock: MockClock,
+    log: CallLog,
+    answers: VecDeque<Result<bool, E>>,
+}
+
+impl<E> ScriptedStopper<E> {
+    pub fn new(clock: MockClock, answers: Vec<Result<bool, E>>) -> Self {
+        Self {
+            clock,
+            log: CallLog::new(),
+            answers: answers.into(),
+        }
+    }
+
+    pub fn log(&self) -> CallLog {
+        self.log.clone()
+    }
+}
+
+impl<E> Stopper for ScriptedStopper<E> {
+    type Error = E;
+
+    fn should_stop(&mut self) -> Result<bool, Self::Error> {
+        self.log.record(self.clock.now_ms());
+        self.answers
+            .pop_front()
+            .expect("ScriptedStopper polled more times than scripted")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use callback::{Callback, Stopper};
+
+    use crate::scripted::{ScriptedCallback, ScriptedStopper};
+    use crate::{assert_called_at, MockClock};
+
+    #[test]
+    fn scripted_stopper_replays_answers() {
+        let clock = MockClock::new();
+        let mut stopper =
+            ScriptedStopper::new(clock.clone(), vec![Ok(false), Err("glitch"), Ok(true)]);
+        let log = stopper.log();
+
+        assert_eq!(stopper.should_stop(), Ok(false));
+        clock.advance_ms(500);
+        assert_eq!(stopper.should_stop(), Err("glitch"));
+        clock.advance_ms(500);
+        assert_eq!(stopper.should_stop(), Ok(true));
+        assert_called_at(&log, &[0, 500, 1000]);
+    }
+
+    #[test]
+    #[should_panic(expected = "more times than scripted")]
+    fn scripted_callback_panics_when_exhausted() {
+        let mut callback = ScriptedCallback::<()>::new(MockClock::new(), vec![Ok(true)]);
+
+        callback.call().unwrap();
+        let _ = callback.call();
+    }
+}
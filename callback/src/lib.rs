@@ -1,10 +1,12 @@
 #![no_std]
 
 pub trait Callback{
-    // Return false when callback aborted, return true else.
-    fn call(&mut self) -> bool;
+    type Error;
+    // Return Ok(false) when callback aborted, Ok(true) else.
+    fn call(&mut self) -> Result<bool, Self::Error>;
 }
 
 pub trait Stopper{
-    fn should_stop(&mut self) -> bool;
-}
\ No newline at end of file
+    type Error;
+    fn should_stop(&mut self) -> Result<bool, Self::Error>;
+}
@@ -0,0 +1,195 @@
+//! Gregorian calendar.
+//!
+//! Months go from 1 to 12 and days of the week from 0 (Sunday) to 6
+//! (Saturday), like the RP2040 RTC.
+
+/// Year from which `DateTime::timestamp` counts.
+pub const EPOCH_YEAR: u16 = 2000;
+
+const SECONDS_IN_A_DAY: u32 = 24 * 3600;
+
+/// Date and time, without time zone.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DateTime {
+    pub year: u16,
+    pub month: u8,
+    pub day: u8,
+    pub hour: u8,
+    pub minute: u8,
+    pub second: u8,
+}
+
+impl DateTime {
+    pub fn day_of_week(&self) -> u8 {
+        day_of_week(self.year, self.month, self.day)
+    }
+
+    /// Whether all the fields are in range, e.g. after decoding them.
+    pub fn is_valid(&self) -> bool {
+        is_valid_date(self.year, self.month, self.day)
+            && self.hour < 24
+            && self.minute < 60
+            && self.second < 60
+    }
+
+    /// Seconds since the start of `EPOCH_YEAR`, for a valid date from then on.
+    pub fn timestamp(&self) -> u32 {
+        let mut days = (self.day - 1) as u32;
+        for year in EPOCH_YEAR..self.year {
+            days += if is_leap_year(year) { 366 } else { 365 };
+        }
+        for month in 1..self.month {
+            days += days_in_month(self.year, month) as u32;
+        }
+        days * SECONDS_IN_A_DAY
+            + self.hour as u32 * 3600
+            + self.minute as u32 * 60
+            + self.second as u32
+    }
+
+    pub fn from_timestamp(timestamp: u32) -> Self {
+        let mut days = timestamp / SECONDS_IN_A_DAY;
+        let seconds = timestamp % SECONDS_IN_A_DAY;
+        let mut year = EPOCH_YEAR;
+        loop {
+            let year_days = if is_leap_year(year) { 366 } else { 365 };
+            if days < year_days {
+                break;
+            }
+            days -= year_days;
+            year += 1;
+        }
+        let mut month = 1;
+        while days >= days_in_month(year, month) as u32 {
+            days -= days_in_month(year, month) as u32;
+            month += 1;
+        }
+        Self {
+            year,
+            month,
+            day: days as u8 + 1,
+            hour: (seconds / 3600) as u8,
+            minute: (seconds / 60 % 60) as u8,
+            second: (seconds % 60) as u8,
+        }
+    }
+}
+
+pub fn is_leap_year(year: u16) -> bool {
+    (year.is_multiple_of(4) && !year.is_multiple_of(100)) || year.is_multiple_of(400)
+}
+
+/// Number of days of `month`, 0 for an invalid month.
+pub fn days_in_month(year: u16, month: u8) -> u8 {
+    match month {
+        1 | 3 | 5 | 7 | 8 | 10 | 12 => 31,
+        4 | 6 | 9 | 11 => 30,
+        2 if is_leap_year(year) => 29,
+        2 => 28,
+        _ => 0,
+    }
+}
+
+pub fn is_valid_date(year: u16, month: u8, day: u8) -> bool {
+    day >= 1 && day <= days_in_month(year, month)
+}
+
+/// Day of the week of a valid date, 0 being Sunday.
+pub fn day_of_week(year: u16, month: u8, day: u8) -> u8 {
+    // Sakamoto's method
+    const OFFSETS: [u16; 12] = [0, 3, 2, 5, 0, 3, 5, 1, 4, 6, 2, 4];
+    let year = if month < 3 { year - 1 } else { year };
+    ((year + year / 4 - year / 100 + year / 400 + OFFSETS[month as usize - 1] + day as u16) % 7)
+        as u8
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calendar::{day_of_week, days_in_month, is_leap_year, is_valid_date, DateTime};
+
+    #[test]
+    fn leap_years() {
+        assert!(is_leap_year(2024));
+        assert!(is_leap_year(2000));
+        assert!(!is_leap_year(2100));
+        assert!(!is_leap_year(2023));
+    }
+
+    #[test]
+    fn month_lengths() {
+        assert_eq!(days_in_month(2023, 2), 28);
+        assert_eq!(days_in_month(2024, 2), 29);
+        assert_eq!(days_in_month(2024, 4), 30);
+        assert_eq!(days_in_month(2024, 12), 31);
+        assert_eq!(days_in_month(2024, 13), 0);
+        assert!(!is_valid_date(2024, 2, 30));
+        assert!(!is_valid_date(2024, 1, 0));
+    }
+
+    #[test]
+    fn weekdays() {
+        // Saturday 1 January 2000
+        assert_eq!(day_of_week(2000, 1, 1), 6);
+        // Thursday 29 February 2024
+        assert_eq!(day_of_week(2024, 2, 29), 4);
+        // Sunday 31 December 2023
+        assert_eq!(day_of_week(2023, 12, 31), 0);
+        // Monday 19 October 2026
+        assert_eq!(day_of_week(2026, 10, 19), 1);
+    }
+
+    #[test]
+    fn out_of_range_fields_are_invalid() {
+        let datetime = DateTime {
+            year: 2024,
+            month: 2,
+            day: 29,
+            hour: 23,
+            minute: 59,
+            second: 59,
+        };
+
+        assert!(datetime.is_valid());
+        assert!(!DateTime {
+            hour: 24,
+            ..datetime
+        }
+        .is_valid());
+        assert!(!DateTime {
+            month: 0,
+            ..datetime
+        }
+        .is_valid());
+        assert!(!DateTime {
+            year: 2023,
+            ..datetime
+        }
+        .is_valid());
+    }
+
+    #[test]
+    fn timestamps_round_trip() {
+        let datetime = DateTime {
+            year: 2026,
+            month: 10,
+            day: 19,
+            hour: 18,
+            minute: 5,
+            second: 42,
+        };
+        let new_year = DateTime {
+            year: 2025,
+            month: 1,
+            day: 1,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        };
+
+        assert_eq!(DateTime::from_timestamp(0).year, 2000);
+        // 2000 to 2024 have 7 leap years
+        assert_eq!(new_year.timestamp(), (25 * 365 + 7) * 86400);
+        assert_eq!(DateTime::from_timestamp(new_year.timestamp() - 1).day, 31);
+        assert_eq!(DateTime::from_timestamp(datetime.timestamp()), datetime);
+    }
+}
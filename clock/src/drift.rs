@@ -0,0 +1,236 @@
+//! Drift of a clock, measured from the times it is set to.
+//!
+//! Each time the clock is set to a trusted time, the offset it had is
+//! recorded. Once the time since the first trusted time is long enough for
+//! the precision of the sources, the offsets give the drift in parts per
+//! billion, which `correction` then compensates by steps of a second. The
+//! estimate is kept across measurements and can be restored, e.g. after a
+//! reboot.
+
+/// Relative precision needed before the drift is measured, 10 ppm.
+pub const RESOLUTION_PPB: i64 = 10_000;
+/// Larger drifts are measurement errors, e.g. a wrong time received.
+pub const MAX_PPB: i32 = 1_000_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum SyncSource {
+    Manual,
+    Radio,
+    Gps,
+}
+
+impl SyncSource {
+    /// How far from the true time a time of this source may be.
+    pub fn precision_sec(self) -> u32 {
+        match self {
+            // Entered to the minute, validated a few seconds late
+            SyncSource::Manual => 30,
+            SyncSource::Radio | SyncSource::Gps => 1,
+        }
+    }
+}
+
+pub struct DriftTracker {
+    // Parts per billion, positive when the clock runs fast
+    ppb: Option<i32>,
+    // Trusted time from which the drift is measured
+    reference: Option<(u32, SyncSource)>,
+    // Sum of the offsets corrected since the reference
+    offset_sec: i64,
+    last_sync: Option<(u32, i32)>,
+    // Clock time from which `correction` steps, and the steps so far
+    step_origin: Option<u32>,
+    stepped_sec: i64,
+}
+
+impl DriftTracker {
+    pub fn new(ppb: Option<i32>) -> Self {
+        Self {
+            ppb,
+            reference: None,
+            offset_sec: 0,
+            last_sync: None,
+            step_origin: None,
+            stepped_sec: 0,
+        }
+    }
+
+    pub fn ppb(&self) -> Option<i32> {
+        self.ppb
+    }
+
+    /// Time of the last sync and offset the clock had then, e.g. `-3` for
+    /// three seconds late.
+    pub fn last_sync(&self) -> Option<(u32, i32)> {
+        self.last_sync
+    }
+
+    /// Records that the clock was set to the timestamp `time`, while it read
+    /// `clock`, `None` if it did not have a time. Returns whether the estimate
+    /// changed, e.g. to save it.
+    pub fn record(&mut self, source: SyncSource, time: u32, clock: Option<u32>) -> bool {
+        self.step_origin = Some(time);
+        self.stepped_sec = 0;
+        let clock = match clock {
+            Some(clock) => clock,
+            None => {
+                self.restart(time, source);
+                return false;
+            }
+        };
+        let offset_sec = clock as i64 - time as i64;
+        self.last_sync = Some((time, offset_sec as i32));
+        let (reference, reference_source) = match self.reference {
+            Some(reference) => reference,
+            None => {
+                self.restart(time, source);
+                return false;
+            }
+        };
+        // A more precise source measures sooner
+        if source.precision_sec() < reference_source.precision_sec() {
+            self.restart(time, source);
+            return false;
+        }
+        self.offset_sec += offset_sec;
+        let elapsed_sec = time.saturating_sub(reference) as i64;
+        let precision_sec = reference_source.precision_sec().max(source.precision_sec()) as i64;
+        if elapsed_sec * RESOLUTION_PPB < precision_sec * 1_000_000_000 {
+            return false;
+        }
+        // The offsets are what the current estimate did not correct
+        let current_ppb = self.ppb.unwrap_or(0);
+        let measured_ppb = current_ppb as i64 + self.offset_sec * 1_000_000_000 / elapsed_sec;
+        self.restart(time, source);
+        if measured_ppb.abs() > MAX_PPB as i64 {
+            return false;
+        }
+        // Half of the error is taken each time, which smoothes the rounding of
+        // the offsets to the second
+        self.ppb = Some(match self.ppb {
+            Some(ppb) => ((ppb as i64 + measured_ppb) / 2) as i32,
+            None => measured_ppb as i32,
+        });
+        true
+    }
+
+    /// Records that the clock was set to an untrusted time, e.g. from another
+    /// clock: nothing is measured, but the corrections start from it.
+    pub fn clock_set(&mut self, clock: u32) {
+        self.step_origin = Some(clock);
+        self.stepped_sec = 0;
+    }
+
+    /// Seconds to add to the clock reading `clock` to compensate the drift,
+    /// mostly 0.
+    pub fn correction(&mut self, clock: u32) -> i32 {
+        let (ppb, origin) = match (self.ppb, self.step_origin) {
+            (Some(ppb), Some(origin)) => (ppb, origin),
+            _ => return 0,
+        };
+        // Elapsed on the clock as if it had not been corrected
+        let elapsed_sec = (clock as i64 - origin as i64 - self.stepped_sec).max(0);
+        let due_sec = -(elapsed_sec * ppb as i64 / 1_000_000_000) - self.stepped_sec;
+        self.stepped_sec += due_sec;
+        due_sec as i32
+    }
+
+    fn restart(&mut self, time: u32, source: SyncSource) {
+        self.reference = Some((time, source));
+        self.offset_sec = 0;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::drift::{DriftTracker, SyncSource};
+
+    const DAY: u32 = 24 * 3600;
+
+    #[test]
+    fn measures_the_drift_once_precise_enough() {
+        let mut tracker = DriftTracker::new(None);
+
+        assert!(!tracker.record(SyncSource::Radio, 1000, Some(1000)));
+        // 50 ppm fast, 4 s after a day is not enough for 10 ppm
+        assert!(!tracker.record(SyncSource::Radio, 1000 + DAY, Some(1004 + DAY)));
+        assert_eq!(tracker.ppb(), None);
+        assert!(tracker.record(SyncSource::Radio, 1000 + 2 * DAY, Some(1004 + 2 * DAY)));
+
+        // 8 s over 2 days
+        assert_eq!(tracker.ppb(), Some(46_296));
+        assert_eq!(tracker.last_sync(), Some((1000 + 2 * DAY, 4)));
+    }
+
+    #[test]
+    fn corrections_step_by_seconds() {
+        let mut tracker = DriftTracker::new(Some(50_000));
+        tracker.record(SyncSource::Gps, 0, None);
+
+        assert_eq!(tracker.correction(19_999), 0);
+        assert_eq!(tracker.correction(20_000), -1);
+        assert_eq!(tracker.correction(20_001), 0);
+        assert_eq!(tracker.correction(DAY), -3);
+        tracker.clock_set(2 * DAY);
+        assert_eq!(tracker.correction(2 * DAY + 20_000), -1);
+        // Slow clocks are moved forward
+        let mut tracker = DriftTracker::new(Some(-50_000));
+        tracker.record(SyncSource::Gps, 0, None);
+        assert_eq!(tracker.correction(DAY), 4);
+    }
+
+    #[test]
+    fn corrected_readings_do_not_step_back() {
+        let mut tracker = DriftTracker::new(Some(50_000));
+        tracker.record(SyncSource::Gps, 0, None);
+
+        // The clock is read back after each correction, like the firmware does
+        let mut corrected_sec = 0i64;
+        for uncorrected in 0..2 * DAY {
+            let step_sec = tracker.correction((uncorrected as i64 + corrected_sec) as u32);
+            assert!(step_sec <= 0);
+            corrected_sec += step_sec as i64;
+            assert_eq!(
+                tracker.correction((uncorrected as i64 + corrected_sec) as u32),
+                0
+            );
+        }
+
+        // 8.64 s over 2 days
+        assert_eq!(corrected_sec, -8);
+    }
+
+    #[test]
+    fn residual_offsets_refine_the_estimate() {
+        let mut tracker = DriftTracker::new(Some(40_000));
+        tracker.record(SyncSource::Radio, 0, None);
+
+        // Still 1 s fast every day despite the corrections, 11.6 ppm more
+        assert!(tracker.record(SyncSource::Radio, 2 * DAY, Some(2 * DAY + 2)));
+
+        assert_eq!(tracker.ppb(), Some(45_787));
+    }
+
+    #[test]
+    fn manual_entries_need_longer() {
+        let mut tracker = DriftTracker::new(None);
+        tracker.record(SyncSource::Manual, 0, Some(0));
+
+        assert!(!tracker.record(SyncSource::Manual, 30 * DAY, Some(30 * DAY + 120)));
+        assert!(tracker.record(SyncSource::Manual, 40 * DAY, Some(40 * DAY + 40)));
+        // A radio time is more precise, it starts over
+        assert!(!tracker.record(SyncSource::Radio, 41 * DAY, Some(41 * DAY + 2)));
+        assert!(tracker.record(SyncSource::Radio, 43 * DAY, Some(43 * DAY + 7)));
+    }
+
+    #[test]
+    fn wrong_times_are_not_drift() {
+        let mut tracker = DriftTracker::new(Some(20_000));
+        tracker.record(SyncSource::Gps, 0, None);
+
+        // An hour off
+        assert!(!tracker.record(SyncSource::Gps, 2 * DAY, Some(2 * DAY + 3600)));
+
+        assert_eq!(tracker.ppb(), Some(20_000));
+    }
+}
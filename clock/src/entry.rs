@@ -0,0 +1,430 @@
+//! Entry of the date and time with two buttons.
+//!
+//! Each field is entered in turn, `Increment` and `Decrement` change the
+//! current field, wrapping around, and `Validate` moves to the next one. Hours
+//! and minutes are entered digit by digit. `TimeEntry` only holds the state;
+//! the firmware feeds it the button events and draws its `EntryView`.
+//!
+//! Nobody may be there to enter the time, e.g. after a power cut: `Timeout`
+//! ends the entry with the initial time, which the firmware sends once
+//! `Inactivity` expires.
+
+use arrayvec::ArrayString;
+use core::fmt::Write;
+
+use crate::calendar::{days_in_month, DateTime};
+
+/// Years offered by the entry.
+pub const FIRST_YEAR: u16 = 2024;
+pub const LAST_YEAR: u16 = 2099;
+
+/// Time without any button press before the entry is abandoned.
+pub const ENTRY_TIMEOUT_MS: u64 = 60_000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Phase {
+    Year,
+    Month,
+    Day,
+    HourTens,
+    HourUnits,
+    MinuteTens,
+    MinuteUnits,
+    Finished,
+}
+
+impl Phase {
+    fn next(self) -> Self {
+        match self {
+            Phase::Year => Phase::Month,
+            Phase::Month => Phase::Day,
+            Phase::Day => Phase::HourTens,
+            Phase::HourTens => Phase::HourUnits,
+            Phase::HourUnits => Phase::MinuteTens,
+            Phase::MinuteTens => Phase::MinuteUnits,
+            Phase::MinuteUnits | Phase::Finished => Phase::Finished,
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum EntryEvent {
+    Increment,
+    Decrement,
+    Validate,
+    /// Ends the entry, keeping the initial time.
+    Timeout,
+}
+
+/// What to show: the field being entered, its value, and the column of the
+/// digit to blink.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct EntryView {
+    pub phase: Phase,
+    pub value: ArrayString<10>,
+    pub cursor: Option<u8>,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeEntry {
+    initial: DateTime,
+    datetime: DateTime,
+    phase: Phase,
+    timed_out: bool,
+}
+
+impl TimeEntry {
+    /// Starts from `initial`, its year is brought into the offered range.
+    pub fn new(initial: DateTime) -> Self {
+        let mut datetime = initial;
+        datetime.year = datetime.year.clamp(FIRST_YEAR, LAST_YEAR);
+        Self {
+            initial,
+            datetime,
+            phase: Phase::Year,
+            timed_out: false,
+        }
+    }
+
+    pub fn phase(&self) -> Phase {
+        self.phase
+    }
+
+    pub fn is_finished(&self) -> bool {
+        self.phase == Phase::Finished
+    }
+
+    pub fn datetime(&self) -> DateTime {
+        self.datetime
+    }
+
+    /// Whether the entry ended without the user.
+    pub fn timed_out(&self) -> bool {
+        self.timed_out
+    }
+
+    pub fn handle(&mut self, event: EntryEvent) {
+        match event {
+            EntryEvent::Increment => self.step(true),
+            EntryEvent::Decrement => self.step(false),
+            EntryEvent::Validate => self.phase = self.phase.next(),
+            EntryEvent::Timeout if !self.is_finished() => {
+                self.datetime = self.initial;
+                self.phase = Phase::Finished;
+                self.timed_out = true;
+            }
+            EntryEvent::Timeout => {}
+        }
+    }
+
+    // The day only goes up to the length of the month and follows changes of
+    // the month or year, so that invalid dates cannot be entered.
+    fn step(&mut self, forward: bool) {
+        let datetime = &mut self.datetime;
+        let (tens, units) = (datetime.hour / 10, datetime.hour % 10);
+        let days = days_in_month(datetime.year, datetime.month);
+        match self.phase {
+            Phase::Year => datetime.year = cycle(datetime.year, FIRST_YEAR, LAST_YEAR, forward),
+            Phase::Month => datetime.month = cycle(datetime.month as u16, 1, 12, forward) as u8,
+            Phase::Day => datetime.day = cycle(datetime.day as u16, 1, days as u16, forward) as u8,
+            // 2x hours stop at 23
+            Phase::HourTens => {
+                let tens = cycle(tens as u16, 0, 2, forward) as u8;
+                datetime.hour = tens * 10 + if tens == 2 { units.min(3) } else { units }
+            }
+            Phase::HourUnits => {
+                let last = if tens == 2 { 3 } else { 9 };
+                datetime.hour = tens * 10 + cycle(units as u16, 0, last, forward) as u8
+            }
+            Phase::MinuteTens => {
+                datetime.minute = cycle(datetime.minute as u16 / 10, 0, 5, forward) as u8 * 10
+                    + datetime.minute % 10
+            }
+            Phase::MinuteUnits => {
+                datetime.minute = datetime.minute / 10 * 10
+                    + cycle(datetime.minute as u16 % 10, 0, 9, forward) as u8
+            }
+            Phase::Finished => {}
+        }
+        datetime.day = datetime
+            .day
+            .min(days_in_month(datetime.year, datetime.month));
+    }
+
+    pub fn view(&self) -> EntryView {
+        let datetime = &self.datetime;
+        let mut value = ArrayString::new();
+        match self.phase {
+            Phase::Year | Phase::Month | Phase::Day => write!(
+                value,
+                "{:0>4}/{:0>2}/{:0>2}",
+                datetime.year, datetime.month, datetime.day
+            )
+            .unwrap(),
+            Phase::HourTens | Phase::HourUnits | Phase::MinuteTens | Phase::MinuteUnits => {
+                write!(value, "{:0>2}:{:0>2}", datetime.hour, datetime.minute).unwrap()
+            }
+            Phase::Finished => {}
+        }
+        let cursor = match self.phase {
+            // yyyy/mm/dd
+            Phase::Year => Some(3),
+            Phase::Month => Some(6),
+            Phase::Day => Some(9),
+            // hh:mm
+            Phase::HourTens => Some(0),
+            Phase::HourUnits => Some(1),
+            Phase::MinuteTens => Some(3),
+            Phase::MinuteUnits => Some(4),
+            Phase::Finished => None,
+        };
+        EntryView {
+            phase: self.phase,
+            value,
+            cursor,
+        }
+    }
+}
+
+// Next or previous value of `min..=max`, wrapping around.
+fn cycle(value: u16, min: u16, max: u16, forward: bool) -> u16 {
+    let count = max - min + 1;
+    let offset = value.clamp(min, max) - min;
+    min + if forward {
+        (offset + 1) % count
+    } else {
+        (offset + count - 1) % count
+    }
+}
+
+/// Tracks the time since the last button press.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Inactivity {
+    timeout_ms: u64,
+    last_activity_ms: u64,
+}
+
+impl Inactivity {
+    pub fn new(timeout_ms: u64, now_ms: u64) -> Self {
+        Self {
+            timeout_ms,
+            last_activity_ms: now_ms,
+        }
+    }
+
+    pub fn touch(&mut self, now_ms: u64) {
+        self.last_activity_ms = now_ms;
+    }
+
+    /// Time at which it expires without any new activity.
+    pub fn deadline_ms(&self) -> u64 {
+        self.last_activity_ms + self.timeout_ms
+    }
+
+    pub fn is_expired(&self, now_ms: u64) -> bool {
+        now_ms >= self.deadline_ms()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calendar::DateTime;
+    use crate::entry::{EntryEvent, Inactivity, Phase, TimeEntry, FIRST_YEAR, LAST_YEAR};
+
+    const START: DateTime = DateTime {
+        year: 2024,
+        month: 1,
+        day: 1,
+        hour: 0,
+        minute: 0,
+        second: 0,
+    };
+
+    fn entry_at(phase: Phase, datetime: DateTime) -> TimeEntry {
+        let mut entry = TimeEntry::new(datetime);
+        while entry.phase() != phase {
+            entry.handle(EntryEvent::Validate);
+        }
+        entry
+    }
+
+    fn press(entry: &mut TimeEntry, times: usize) {
+        for _ in 0..times {
+            entry.handle(EntryEvent::Increment);
+        }
+    }
+
+    #[test]
+    fn hour_units_roll_over_at_23() {
+        let mut entry = entry_at(Phase::HourUnits, DateTime { hour: 20, ..START });
+
+        press(&mut entry, 3);
+        assert_eq!(entry.datetime().hour, 23);
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().hour, 20);
+
+        let mut entry = entry_at(Phase::HourUnits, DateTime { hour: 19, ..START });
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().hour, 10);
+    }
+
+    #[test]
+    fn hour_tens_never_give_an_invalid_hour() {
+        let mut entry = entry_at(Phase::HourTens, DateTime { hour: 9, ..START });
+
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().hour, 19);
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().hour, 23);
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().hour, 3);
+    }
+
+    #[test]
+    fn minutes_digits() {
+        let mut entry = entry_at(
+            Phase::MinuteTens,
+            DateTime {
+                minute: 59,
+                ..START
+            },
+        );
+
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().minute, 9);
+        entry.handle(EntryEvent::Validate);
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().minute, 0);
+    }
+
+    #[test]
+    fn february_30_cannot_be_entered() {
+        let mut entry = entry_at(Phase::Month, DateTime { day: 31, ..START });
+
+        press(&mut entry, 1);
+        assert_eq!((entry.datetime().month, entry.datetime().day), (2, 29));
+        entry.handle(EntryEvent::Validate);
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().day, 1);
+
+        let mut entry = entry_at(
+            Phase::Year,
+            DateTime {
+                month: 2,
+                day: 29,
+                ..START
+            },
+        );
+        press(&mut entry, 1);
+        assert_eq!(entry.datetime().day, 28);
+    }
+
+    #[test]
+    fn years_wrap_around() {
+        let mut entry = TimeEntry::new(DateTime { year: 0, ..START });
+        assert_eq!(entry.datetime().year, FIRST_YEAR);
+
+        press(&mut entry, (LAST_YEAR - FIRST_YEAR) as usize + 1);
+
+        assert_eq!(entry.datetime().year, FIRST_YEAR);
+    }
+
+    #[test]
+    fn view_follows_the_phases() {
+        let mut entry = TimeEntry::new(DateTime {
+            year: 2026,
+            month: 10,
+            day: 19,
+            hour: 18,
+            minute: 5,
+            second: 0,
+        });
+
+        let view = entry.view();
+        assert_eq!((view.value.as_str(), view.cursor), ("2026/10/19", Some(3)));
+        for _ in 0..4 {
+            entry.handle(EntryEvent::Validate);
+        }
+        let view = entry.view();
+        assert_eq!(view.phase, Phase::HourUnits);
+        assert_eq!((view.value.as_str(), view.cursor), ("18:05", Some(1)));
+        for _ in 0..3 {
+            entry.handle(EntryEvent::Validate);
+        }
+        assert!(entry.is_finished());
+        assert_eq!(entry.view().cursor, None);
+    }
+
+    #[test]
+    fn decrement_wraps_backwards() {
+        let mut entry = TimeEntry::new(START);
+
+        entry.handle(EntryEvent::Decrement);
+        assert_eq!(entry.datetime().year, LAST_YEAR);
+        entry.handle(EntryEvent::Validate);
+        entry.handle(EntryEvent::Decrement);
+        assert_eq!(entry.datetime().month, 12);
+        entry.handle(EntryEvent::Validate);
+        entry.handle(EntryEvent::Decrement);
+        assert_eq!(entry.datetime().day, 31);
+    }
+
+    #[test]
+    fn decrement_of_hours_and_minutes() {
+        let mut entry = entry_at(Phase::HourTens, DateTime { hour: 9, ..START });
+
+        entry.handle(EntryEvent::Decrement);
+        assert_eq!(entry.datetime().hour, 23);
+        entry.handle(EntryEvent::Validate);
+        entry.handle(EntryEvent::Decrement);
+        assert_eq!(entry.datetime().hour, 22);
+        entry.handle(EntryEvent::Increment);
+        entry.handle(EntryEvent::Increment);
+        assert_eq!(entry.datetime().hour, 20);
+        entry.handle(EntryEvent::Validate);
+        entry.handle(EntryEvent::Decrement);
+        assert_eq!(entry.datetime().minute, 50);
+        entry.handle(EntryEvent::Validate);
+        entry.handle(EntryEvent::Decrement);
+        assert_eq!(entry.datetime().minute, 59);
+    }
+
+    #[test]
+    fn timeout_keeps_the_initial_time() {
+        let initial = DateTime {
+            year: 2026,
+            month: 10,
+            day: 19,
+            hour: 18,
+            minute: 5,
+            second: 42,
+        };
+        let mut entry = TimeEntry::new(initial);
+        entry.handle(EntryEvent::Increment);
+
+        entry.handle(EntryEvent::Timeout);
+
+        assert!(entry.is_finished() && entry.timed_out());
+        assert_eq!(entry.datetime(), initial);
+    }
+
+    #[test]
+    fn timeout_after_the_end_changes_nothing() {
+        let mut entry = entry_at(Phase::Finished, START);
+
+        entry.handle(EntryEvent::Timeout);
+
+        assert!(!entry.timed_out());
+    }
+
+    #[test]
+    fn inactivity_restarts_on_activity() {
+        let mut inactivity = Inactivity::new(1000, 0);
+
+        inactivity.touch(800);
+
+        assert!(!inactivity.is_expired(1500));
+        assert!(inactivity.is_expired(1800));
+        assert_eq!(inactivity.deadline_ms(), 1800);
+    }
+}
@@ -0,0 +1,11 @@
+#![cfg_attr(not(test), no_std)]
+
+//! Date and time computations, independent of the RTC peripheral.
+
+pub mod calendar;
+pub mod drift;
+pub mod entry;
+pub mod timezone;
+
+pub use calendar::DateTime;
+pub use timezone::TimeZone;
@@ -0,0 +1,431 @@
+//! Time zones with rule-based daylight saving time.
+//!
+//! The clocks keep the UTC time, which never jumps; a `TimeZone` gives the
+//! local time from it and back. Daylight saving time starts and ends on a
+//! given weekday of a month each year, like the `Mm.w.d` rules of POSIX `TZ`
+//! strings, e.g. `CET-1CEST,M3.5.0,M10.5.0/3` for the EU_CENTRAL rules.
+
+use crate::calendar::{day_of_week, days_in_month, DateTime};
+
+/// Central European time, summer time from the last Sunday of March to the
+/// last Sunday of October, at 01:00 UTC.
+pub const EU_CENTRAL: TimeZone = TimeZone {
+    offset_sec: 3600,
+    dst: Some(Dst {
+        offset_sec: 2 * 3600,
+        start: Transition {
+            month: 3,
+            week: 5,
+            weekday: 0,
+            time_sec: 2 * 3600,
+        },
+        end: Transition {
+            month: 10,
+            week: 5,
+            weekday: 0,
+            time_sec: 3 * 3600,
+        },
+    }),
+};
+
+pub const UTC: TimeZone = TimeZone {
+    offset_sec: 0,
+    dst: None,
+};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum TzError {
+    // Missing or invalid name of the standard or daylight saving time
+    BadName,
+    BadOffset,
+    // Daylight saving time without start and end
+    MissingRule,
+    // Only the `Mm.w.d` rules are known
+    UnsupportedRule,
+    BadRule,
+}
+
+/// Day and local time at which daylight saving time starts or ends.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Transition {
+    pub month: u8,
+    /// From 1 to 5, 5 being the last week of the month
+    pub week: u8,
+    /// 0 being Sunday
+    pub weekday: u8,
+    /// Seconds since midnight, in the local time in effect before the
+    /// transition; may be negative or beyond a day
+    pub time_sec: i32,
+}
+
+impl Transition {
+    /// UTC timestamp of the transition in `year`, as a signed number so that
+    /// transitions close to the epoch stay ordered.
+    fn utc_timestamp(&self, year: u16, offset_before_sec: i32) -> i64 {
+        let first_weekday = day_of_week(year, self.month, 1);
+        let mut day = 1 + (self.weekday + 7 - first_weekday) % 7 + (self.week - 1) * 7;
+        while day > days_in_month(year, self.month) {
+            day -= 7;
+        }
+        let midnight = DateTime {
+            year,
+            month: self.month,
+            day,
+            hour: 0,
+            minute: 0,
+            second: 0,
+        };
+        midnight.timestamp() as i64 + self.time_sec as i64 - offset_before_sec as i64
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Dst {
+    /// Offset of the daylight saving time to UTC, east being positive
+    pub offset_sec: i32,
+    pub start: Transition,
+    pub end: Transition,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TimeZone {
+    /// Offset of the standard time to UTC, east being positive
+    pub offset_sec: i32,
+    pub dst: Option<Dst>,
+}
+
+impl TimeZone {
+    /// Parses a POSIX `TZ` string, e.g. `EST5EDT,M3.2.0,M11.1.0`. Note that
+    /// POSIX offsets are west of UTC, the opposite of `offset_sec`.
+    pub fn from_posix(tz: &str) -> Result<Self, TzError> {
+        let mut parser = Parser {
+            rest: tz.as_bytes(),
+        };
+        parser.name()?;
+        let offset_sec = -parser.duration().ok_or(TzError::BadOffset)?;
+        if parser.rest.is_empty() {
+            return Ok(Self {
+                offset_sec,
+                dst: None,
+            });
+        }
+        parser.name()?;
+        let dst_offset_sec = match parser.rest.first() {
+            Some(b',') | None => offset_sec + 3600,
+            Some(_) => -parser.duration().ok_or(TzError::BadOffset)?,
+        };
+        if parser.rest.is_empty() {
+            return Err(TzError::MissingRule);
+        }
+        parser.expect(b',').ok_or(TzError::BadRule)?;
+        let start = parser.transition()?;
+        parser.expect(b',').ok_or(TzError::BadRule)?;
+        let end = parser.transition()?;
+        if !parser.rest.is_empty() {
+            return Err(TzError::BadRule);
+        }
+        Ok(Self {
+            offset_sec,
+            dst: Some(Dst {
+                offset_sec: dst_offset_sec,
+                start,
+                end,
+            }),
+        })
+    }
+
+    /// Whether daylight saving time is in effect at the UTC `timestamp`.
+    pub fn is_dst(&self, timestamp: u32) -> bool {
+        let dst = match self.dst {
+            Some(dst) => dst,
+            None => return false,
+        };
+        let year = DateTime::from_timestamp(timestamp).year;
+        let start = dst.start.utc_timestamp(year, self.offset_sec);
+        let end = dst.end.utc_timestamp(year, dst.offset_sec);
+        let timestamp = timestamp as i64;
+        if start < end {
+            timestamp >= start && timestamp < end
+        } else {
+            // Southern hemisphere, over the new year
+            timestamp >= start || timestamp < end
+        }
+    }
+
+    /// Offset to UTC at the UTC `timestamp`, east being positive.
+    pub fn offset_at(&self, timestamp: u32) -> i32 {
+        match self.dst {
+            Some(dst) if self.is_dst(timestamp) => dst.offset_sec,
+            _ => self.offset_sec,
+        }
+    }
+
+    pub fn to_local(&self, utc: &DateTime) -> DateTime {
+        let timestamp = utc.timestamp();
+        DateTime::from_timestamp(shift(timestamp, self.offset_at(timestamp)))
+    }
+
+    /// UTC time of a local time. Local times repeated when daylight saving
+    /// time ends are taken in daylight saving time, the first ones; the ones
+    /// skipped when it starts are taken in standard time, i.e. an hour later.
+    pub fn to_utc(&self, local: &DateTime) -> DateTime {
+        let timestamp = local.timestamp();
+        if let Some(dst) = self.dst {
+            let utc = shift(timestamp, -dst.offset_sec);
+            if self.is_dst(utc) {
+                return DateTime::from_timestamp(utc);
+            }
+        }
+        DateTime::from_timestamp(shift(timestamp, -self.offset_sec))
+    }
+}
+
+// Saturates at the epoch, whose local time may be before it.
+fn shift(timestamp: u32, offset_sec: i32) -> u32 {
+    (timestamp as i64 + offset_sec as i64).max(0) as u32
+}
+
+struct Parser<'a> {
+    rest: &'a [u8],
+}
+
+impl<'a> Parser<'a> {
+    fn expect(&mut self, byte: u8) -> Option<()> {
+        match self.rest.split_first() {
+            Some((&first, rest)) if first == byte => {
+                self.rest = rest;
+                Some(())
+            }
+            _ => None,
+        }
+    }
+
+    // Alphabetic name of at least 3 letters, or any in `<>` like `<+0330>`
+    fn name(&mut self) -> Result<(), TzError> {
+        let length = if self.expect(b'<').is_some() {
+            let length = self
+                .rest
+                .iter()
+                .position(|&byte| byte == b'>')
+                .ok_or(TzError::BadName)?;
+            self.rest = &self.rest[length + 1..];
+            length
+        } else {
+            let length = self
+                .rest
+                .iter()
+                .take_while(|byte| byte.is_ascii_alphabetic())
+                .count();
+            self.rest = &self.rest[length..];
+            length
+        };
+        if length < 3 {
+            return Err(TzError::BadName);
+        }
+        Ok(())
+    }
+
+    fn number(&mut self) -> Option<i32> {
+        let length = self
+            .rest
+            .iter()
+            .take_while(|byte| byte.is_ascii_digit())
+            .count();
+        if length == 0 || length > 3 {
+            return None;
+        }
+        let (digits, rest) = self.rest.split_at(length);
+        self.rest = rest;
+        Some(
+            digits
+                .iter()
+                .fold(0, |number, digit| number * 10 + (digit - b'0') as i32),
+        )
+    }
+
+    // `[+-]hh[:mm[:ss]]`, in seconds
+    fn duration(&mut self) -> Option<i32> {
+        let sign = if self.expect(b'-').is_some() {
+            -1
+        } else {
+            self.expect(b'+');
+            1
+        };
+        let mut seconds = self.number()? * 3600;
+        for unit in [60, 1] {
+            if self.expect(b':').is_none() {
+                break;
+            }
+            let value = self.number()?;
+            if value >= 60 {
+                return None;
+            }
+            seconds += value * unit;
+        }
+        Some(sign * seconds)
+    }
+
+    // `Mm.w.d[/time]`
+    fn transition(&mut self) -> Result<Transition, TzError> {
+        if self.expect(b'M').is_none() {
+            return match self.rest.first() {
+                Some(b'J') | Some(b'0'..=b'9') => Err(TzError::UnsupportedRule),
+                _ => Err(TzError::BadRule),
+            };
+        }
+        let mut field = |separator: Option<u8>, range: core::ops::RangeInclusive<i32>| {
+            if let Some(separator) = separator {
+                self.expect(separator)?;
+            }
+            self.number().filter(|value| range.contains(value))
+        };
+        let month = field(None, 1..=12).ok_or(TzError::BadRule)?;
+        let week = field(Some(b'.'), 1..=5).ok_or(TzError::BadRule)?;
+        let weekday = field(Some(b'.'), 0..=6).ok_or(TzError::BadRule)?;
+        let time_sec = if self.expect(b'/').is_some() {
+            self.duration().ok_or(TzError::BadRule)?
+        } else {
+            2 * 3600
+        };
+        Ok(Transition {
+            month: month as u8,
+            week: week as u8,
+            weekday: weekday as u8,
+            time_sec,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::calendar::DateTime;
+    use crate::timezone::{TimeZone, Transition, TzError, EU_CENTRAL, UTC};
+
+    fn datetime(year: u16, month: u8, day: u8, hour: u8, minute: u8) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second: 0,
+        }
+    }
+
+    #[test]
+    fn eu_summer_time_changes_at_one_utc() {
+        // Last Sundays of 2026: March 29th and October 25th
+        let zone = EU_CENTRAL;
+
+        assert_eq!(
+            zone.to_local(&datetime(2026, 3, 29, 0, 59)),
+            datetime(2026, 3, 29, 1, 59)
+        );
+        assert_eq!(
+            zone.to_local(&datetime(2026, 3, 29, 1, 0)),
+            datetime(2026, 3, 29, 3, 0)
+        );
+        assert_eq!(
+            zone.to_local(&datetime(2026, 10, 25, 0, 59)),
+            datetime(2026, 10, 25, 2, 59)
+        );
+        assert_eq!(
+            zone.to_local(&datetime(2026, 10, 25, 1, 0)),
+            datetime(2026, 10, 25, 2, 0)
+        );
+        assert_eq!(
+            zone.to_local(&datetime(2026, 12, 31, 23, 30)),
+            datetime(2027, 1, 1, 0, 30)
+        );
+    }
+
+    #[test]
+    fn local_times_around_the_changes() {
+        let zone = EU_CENTRAL;
+
+        // An 18:00 alarm stays at 18:00 on both sides of the change
+        assert_eq!(
+            zone.to_utc(&datetime(2026, 3, 28, 18, 0)),
+            datetime(2026, 3, 28, 17, 0)
+        );
+        assert_eq!(
+            zone.to_utc(&datetime(2026, 3, 30, 18, 0)),
+            datetime(2026, 3, 30, 16, 0)
+        );
+        // Skipped, taken an hour later
+        assert_eq!(
+            zone.to_utc(&datetime(2026, 3, 29, 2, 30)),
+            datetime(2026, 3, 29, 1, 30)
+        );
+        // Repeated, the first one is taken
+        assert_eq!(
+            zone.to_utc(&datetime(2026, 10, 25, 2, 30)),
+            datetime(2026, 10, 25, 0, 30)
+        );
+        assert_eq!(
+            UTC.to_utc(&datetime(2026, 6, 1, 12, 0)),
+            datetime(2026, 6, 1, 12, 0)
+        );
+    }
+
+    #[test]
+    fn parses_posix_strings() {
+        assert_eq!(
+            TimeZone::from_posix("CET-1CEST,M3.5.0,M10.5.0/3"),
+            Ok(EU_CENTRAL)
+        );
+        assert_eq!(TimeZone::from_posix("UTC0"), Ok(UTC));
+        assert_eq!(
+            TimeZone::from_posix("<+0530>-5:30"),
+            Ok(TimeZone {
+                offset_sec: 5 * 3600 + 30 * 60,
+                dst: None,
+            })
+        );
+
+        let new_york = TimeZone::from_posix("EST5EDT,M3.2.0,M11.1.0").unwrap();
+        assert_eq!(new_york.offset_sec, -5 * 3600);
+        assert_eq!(new_york.dst.unwrap().offset_sec, -4 * 3600);
+        assert_eq!(
+            new_york.dst.unwrap().start,
+            Transition {
+                month: 3,
+                week: 2,
+                weekday: 0,
+                time_sec: 2 * 3600,
+            }
+        );
+        // Second Sunday of March 2026, 02:00 EST
+        assert_eq!(
+            new_york.to_local(&datetime(2026, 3, 8, 7, 0)),
+            datetime(2026, 3, 8, 3, 0)
+        );
+    }
+
+    #[test]
+    fn southern_summer_spans_the_new_year() {
+        let sydney = TimeZone::from_posix("AEST-10AEDT,M10.1.0,M4.1.0/3").unwrap();
+
+        assert!(sydney.is_dst(datetime(2026, 1, 15, 0, 0).timestamp()));
+        assert!(!sydney.is_dst(datetime(2026, 7, 15, 0, 0).timestamp()));
+        assert_eq!(
+            sydney.to_local(&datetime(2026, 12, 31, 13, 0)),
+            datetime(2027, 1, 1, 0, 0)
+        );
+    }
+
+    #[test]
+    fn rejects_bad_posix_strings() {
+        assert_eq!(TimeZone::from_posix("C-1"), Err(TzError::BadName));
+        assert_eq!(TimeZone::from_posix("CET"), Err(TzError::BadOffset));
+        assert_eq!(TimeZone::from_posix("CET-1CEST"), Err(TzError::MissingRule));
+        assert_eq!(
+            TimeZone::from_posix("CET-1CEST,J60,J300"),
+            Err(TzError::UnsupportedRule)
+        );
+        assert_eq!(
+            TimeZone::from_posix("CET-1CEST,M13.5.0,M10.5.0"),
+            Err(TzError::BadRule)
+        );
+    }
+}
@@ -0,0 +1,423 @@
+//! Animations of the RGB backlight.
+//!
+//! An `Animation` gives the colour of the backlight at any time after its
+//! start, using integer math only. `Backlight` plays one animation from a
+//! millisecond counter, like the RP2040 `Timer`, and only sends the colour
+//! to the display when it changes. Its brightness can be lowered with
+//! `set_level`, see `power`.
+
+use crate::TextDisplay;
+
+pub type Rgb = (u8, u8, u8);
+
+pub const BLACK: Rgb = (0, 0, 0);
+
+/// Colour reached `at_ms` after the start of a keyframe animation.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Keyframe {
+    pub at_ms: u32,
+    pub color: Rgb,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Animation {
+    Solid(Rgb),
+    /// Linear interpolation between keyframes sorted by time. The colour of
+    /// the last keyframe is kept, unless `looping` restarts from the first.
+    Keyframes {
+        frames: &'static [Keyframe],
+        looping: bool,
+    },
+    /// Hue cycle through red, green and blue.
+    Rainbow {
+        period_ms: u32,
+    },
+    /// Brightness slowly rising from off to `color` and back.
+    Breathing {
+        color: Rgb,
+        period_ms: u32,
+    },
+    /// Flash at full brightness decaying to a quarter of it in `decay_ms`.
+    Pulse {
+        color: Rgb,
+        period_ms: u32,
+        decay_ms: u32,
+    },
+    /// Transition from one colour to another, then stays on `to`.
+    Fade {
+        from: Rgb,
+        to: Rgb,
+        duration_ms: u32,
+    },
+    /// On for the first half of each period, off for the second.
+    Blink {
+        color: Rgb,
+        period_ms: u32,
+    },
+}
+
+impl Animation {
+    /// Colour of single colour animations, the target one for a fade.
+    pub fn color(&self) -> Option<Rgb> {
+        match *self {
+            Animation::Solid(color)
+            | Animation::Breathing { color, .. }
+            | Animation::Pulse { color, .. }
+            | Animation::Blink { color, .. }
+            | Animation::Fade { to: color, .. } => Some(color),
+            Animation::Keyframes { .. } | Animation::Rainbow { .. } => None,
+        }
+    }
+
+    /// Same animation in another colour, multicolour animations are unchanged.
+    pub fn with_color(self, new_color: Rgb) -> Self {
+        match self {
+            Animation::Solid(_) => Animation::Solid(new_color),
+            Animation::Breathing { period_ms, .. } => Animation::Breathing {
+                color: new_color,
+                period_ms,
+            },
+            Animation::Pulse {
+                period_ms,
+                decay_ms,
+                ..
+            } => Animation::Pulse {
+                color: new_color,
+                period_ms,
+                decay_ms,
+            },
+            Animation::Blink { period_ms, .. } => Animation::Blink {
+                color: new_color,
+                period_ms,
+            },
+            Animation::Fade {
+                from, duration_ms, ..
+            } => Animation::Fade {
+                from,
+                to: new_color,
+                duration_ms,
+            },
+            Animation::Keyframes { .. } | Animation::Rainbow { .. } => self,
+        }
+    }
+
+    pub fn color_at(&self, elapsed_ms: u64) -> Rgb {
+        match *self {
+            Animation::Solid(color) => color,
+            Animation::Keyframes { frames, looping } => keyframes_at(frames, looping, elapsed_ms),
+            Animation::Rainbow { period_ms } => rainbow_at(phase(elapsed_ms, period_ms), period_ms),
+            Animation::Breathing { color, period_ms } => {
+                let half = (period_ms / 2).max(1);
+                let t = phase(elapsed_ms, period_ms);
+                let level = if t < half {
+                    t * 255 / half
+                } else {
+                    (period_ms - t) * 255 / half
+                };
+                // Squared so that the change looks even to the eye.
+                scale(color, level.min(255) * level.min(255) / 255)
+            }
+            Animation::Pulse {
+                color,
+                period_ms,
+                decay_ms,
+            } => {
+                let t = phase(elapsed_ms, period_ms);
+                let level = if t < decay_ms {
+                    255 - t * 192 / decay_ms
+                } else {
+                    63
+                };
+                scale(color, level)
+            }
+            Animation::Fade {
+                from,
+                to,
+                duration_ms,
+            } => {
+                if elapsed_ms >= duration_ms as u64 {
+                    to
+                } else {
+                    mix(from, to, elapsed_ms as u32, duration_ms)
+                }
+            }
+            Animation::Blink { color, period_ms } => {
+                if phase(elapsed_ms, period_ms) < period_ms / 2 {
+                    color
+                } else {
+                    BLACK
+                }
+            }
+        }
+    }
+}
+
+fn phase(elapsed_ms: u64, period_ms: u32) -> u32 {
+    (elapsed_ms % period_ms.max(1) as u64) as u32
+}
+
+// `level` out of 255
+fn scale(color: Rgb, level: u32) -> Rgb {
+    let channel = |c: u8| (c as u32 * level / 255) as u8;
+    (channel(color.0), channel(color.1), channel(color.2))
+}
+
+// Colour `position / length` of the way from `from` to `to`.
+fn mix(from: Rgb, to: Rgb, position: u32, length: u32) -> Rgb {
+    let channel = |a: u8, b: u8| {
+        let (a, b) = (a as i64, b as i64);
+        (a + (b - a) * position as i64 / length.max(1) as i64) as u8
+    };
+    (
+        channel(from.0, to.0),
+        channel(from.1, to.1),
+        channel(from.2, to.2),
+    )
+}
+
+fn keyframes_at(frames: &[Keyframe], looping: bool, elapsed_ms: u64) -> Rgb {
+    let (first, last) = match (frames.first(), frames.last()) {
+        (Some(first), Some(last)) => (first, last),
+        _ => return BLACK,
+    };
+    let t = if looping && last.at_ms > 0 {
+        elapsed_ms % last.at_ms as u64
+    } else {
+        elapsed_ms
+    };
+    if t <= first.at_ms as u64 {
+        return first.color;
+    }
+    for pair in frames.windows(2) {
+        let (start, end) = (pair[0], pair[1]);
+        if t < end.at_ms as u64 {
+            return mix(
+                start.color,
+                end.color,
+                t as u32 - start.at_ms,
+                end.at_ms - start.at_ms,
+            );
+        }
+    }
+    last.color
+}
+
+fn rainbow_at(t: u32, period_ms: u32) -> Rgb {
+    // Six segments of 255 steps, one channel changing in each.
+    let position = (t as u64 * 6 * 255 / period_ms.max(1) as u64) as u32;
+    let rising = (position % 255) as u8;
+    let falling = 255 - rising;
+    match position / 255 {
+        0 => (255, rising, 0),
+        1 => (falling, 255, 0),
+        2 => (0, 255, rising),
+        3 => (0, falling, 255),
+        4 => (rising, 0, 255),
+        _ => (255, 0, falling),
+    }
+}
+
+/// Plays an animation on the backlight of a display.
+#[derive(Debug)]
+pub struct Backlight {
+    animation: Animation,
+    start_ms: u64,
+    color: Option<Rgb>,
+    // Out of 255
+    level: u8,
+}
+
+impl Backlight {
+    pub fn new(animation: Animation, now_ms: u64) -> Self {
+        Self {
+            animation,
+            start_ms: now_ms,
+            color: None,
+            level: 255,
+        }
+    }
+
+    pub fn play(&mut self, animation: Animation, now_ms: u64) {
+        self.animation = animation;
+        self.start_ms = now_ms;
+    }
+
+    /// Fades from the colour currently shown to `color`.
+    pub fn fade_to(&mut self, color: Rgb, duration_ms: u32, now_ms: u64) {
+        let from = self.color.unwrap_or(BLACK);
+        self.play(
+            Animation::Fade {
+                from,
+                to: color,
+                duration_ms,
+            },
+            now_ms,
+        );
+    }
+
+    pub fn animation(&self) -> &Animation {
+        &self.animation
+    }
+
+    pub fn level(&self) -> u8 {
+        self.level
+    }
+
+    /// Scales the colours of the animation by `level` out of 255.
+    pub fn set_level(&mut self, level: u8) {
+        self.level = level;
+    }
+
+    /// Last colour sent to the display.
+    pub fn color(&self) -> Option<Rgb> {
+        self.color
+    }
+
+    /// Sends the colour of the animation at `now_ms`, if it changed.
+    pub fn update<D: TextDisplay>(&mut self, display: &mut D, now_ms: u64) -> Result<(), D::Error> {
+        let color = scale(
+            self.animation
+                .color_at(now_ms.saturating_sub(self.start_ms)),
+            self.level as u32,
+        );
+        if self.color != Some(color) {
+            display.set_rgb(color.0, color.1, color.2)?;
+            self.color = Some(color);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::backlight::{Animation, Backlight, Keyframe, BLACK};
+    use crate::{MemoryDisplay, TextDisplay};
+
+    const GREEN: (u8, u8, u8) = (0, 255, 0);
+
+    #[test]
+    fn rainbow_cycles_through_hues() {
+        let rainbow = Animation::Rainbow { period_ms: 6000 };
+
+        assert_eq!(rainbow.color_at(0), (255, 0, 0));
+        assert_eq!(rainbow.color_at(500), (255, 127, 0));
+        assert_eq!(rainbow.color_at(2000), (0, 255, 0));
+        assert_eq!(rainbow.color_at(4000), (0, 0, 255));
+        assert_eq!(rainbow.color_at(6000), (255, 0, 0));
+    }
+
+    #[test]
+    fn keyframes_interpolate_and_hold() {
+        static FRAMES: [Keyframe; 3] = [
+            Keyframe {
+                at_ms: 0,
+                color: (0, 0, 0),
+            },
+            Keyframe {
+                at_ms: 100,
+                color: (200, 100, 0),
+            },
+            Keyframe {
+                at_ms: 300,
+                color: (0, 100, 200),
+            },
+        ];
+        let once = Animation::Keyframes {
+            frames: &FRAMES,
+            looping: false,
+        };
+        let looping = Animation::Keyframes {
+            frames: &FRAMES,
+            looping: true,
+        };
+
+        assert_eq!(once.color_at(50), (100, 50, 0));
+        assert_eq!(once.color_at(200), (100, 100, 100));
+        assert_eq!(once.color_at(1000), (0, 100, 200));
+        assert_eq!(looping.color_at(350), (100, 50, 0));
+    }
+
+    #[test]
+    fn breathing_pulse_and_blink() {
+        let breathing = Animation::Breathing {
+            color: GREEN,
+            period_ms: 2000,
+        };
+        let pulse = Animation::Pulse {
+            color: GREEN,
+            period_ms: 1000,
+            decay_ms: 400,
+        };
+        let blink = Animation::Blink {
+            color: GREEN,
+            period_ms: 500,
+        };
+
+        assert_eq!(breathing.color_at(0), BLACK);
+        assert_eq!(breathing.color_at(500), (0, 63, 0));
+        assert_eq!(breathing.color_at(1000), GREEN);
+        assert_eq!(pulse.color_at(1000), GREEN);
+        assert_eq!(pulse.color_at(1200), (0, 159, 0));
+        assert_eq!(pulse.color_at(1700), (0, 63, 0));
+        assert_eq!(blink.color_at(200), GREEN);
+        assert_eq!(blink.color_at(300), BLACK);
+    }
+
+    #[test]
+    fn recolouring_keeps_the_timing() {
+        let pulse = Animation::Pulse {
+            color: GREEN,
+            period_ms: 1000,
+            decay_ms: 400,
+        };
+
+        let yellow = pulse.with_color((255, 255, 0));
+
+        assert_eq!(yellow.color(), Some((255, 255, 0)));
+        assert_eq!(yellow.color_at(1200), (159, 159, 0));
+        let rainbow = Animation::Rainbow { period_ms: 6000 };
+        assert_eq!(rainbow.with_color(GREEN), rainbow);
+        assert_eq!(rainbow.color(), None);
+    }
+
+    #[test]
+    fn fade_to_starts_from_the_shown_colour() {
+        let mut display = MemoryDisplay::new();
+        let mut backlight = Backlight::new(Animation::Solid((255, 255, 0)), 0);
+        backlight.update(&mut display, 10).unwrap();
+
+        backlight.fade_to((255, 0, 0), 1000, 1000);
+        backlight.update(&mut display, 1500).unwrap();
+
+        assert_eq!(display.rgb(), (255, 128, 0));
+        backlight.update(&mut display, 5000).unwrap();
+        assert_eq!(display.rgb(), (255, 0, 0));
+    }
+
+    #[test]
+    fn level_scales_the_animation() {
+        let mut display = MemoryDisplay::new();
+        let mut backlight = Backlight::new(Animation::Solid(GREEN), 0);
+
+        backlight.set_level(64);
+        backlight.update(&mut display, 0).unwrap();
+
+        assert_eq!(display.rgb(), (0, 64, 0));
+        backlight.set_level(0);
+        backlight.update(&mut display, 10).unwrap();
+        assert_eq!(display.rgb(), BLACK);
+    }
+
+    #[test]
+    fn unchanged_colour_is_not_sent_again() {
+        let mut display = MemoryDisplay::new();
+        let mut backlight = Backlight::new(Animation::Solid(GREEN), 0);
+
+        backlight.update(&mut display, 0).unwrap();
+        display.set_rgb(1, 2, 3).unwrap();
+        backlight.update(&mut display, 20).unwrap();
+
+        assert_eq!(display.rgb(), (1, 2, 3));
+        assert_eq!(backlight.color(), Some(GREEN));
+    }
+}
@@ -0,0 +1,159 @@
+//! Encoding of UTF-8 text into the character ROM of the HD44780 (A00 version,
+//! also used by the AiP31068 of the 1602 RGB module).
+//!
+//! The ROM matches ASCII from 0x20 to 0x7D, except for `\` and `~` which are
+//! replaced by `¥` and `→`. Its upper half holds Japanese katakana and a few
+//! Greek and European symbols. Characters missing from the ROM are
+//! transliterated to a single ASCII character, e.g. `é` is shown as `e` and
+//! `œ` as `o`, so that each character takes one column; the remaining ones
+//! are shown as `?`.
+//!
+//! Codes 0 to 7 are kept as is, they display the custom glyphs.
+
+use crate::glyphs::CGRAM_SLOTS;
+
+/// Written for characters that have no equivalent.
+pub const UNKNOWN: u8 = b'?';
+
+/// Code of the character in the ROM, if it has one.
+pub fn rom_code(c: char) -> Option<u8> {
+    match c {
+        c if (c as u32) < CGRAM_SLOTS as u32 => Some(c as u8),
+        '\\' | '~' => None,
+        ' '..='}' => Some(c as u8),
+        '¥' => Some(0x5C),
+        '→' => Some(0x7E),
+        '←' => Some(0x7F),
+        '·' => Some(0xA5),
+        'α' => Some(0xE0),
+        'ä' => Some(0xE1),
+        'β' | 'ß' => Some(0xE2),
+        'ε' => Some(0xE3),
+        'µ' | 'μ' => Some(0xE4),
+        'σ' => Some(0xE5),
+        'ρ' => Some(0xE6),
+        '√' => Some(0xE8),
+        '¢' => Some(0xEC),
+        'ñ' => Some(0xEE),
+        'ö' => Some(0xEF),
+        'θ' => Some(0xF2),
+        '∞' => Some(0xF3),
+        'Ω' => Some(0xF4),
+        'ü' => Some(0xF5),
+        'Σ' => Some(0xF6),
+        'π' => Some(0xF7),
+        '÷' => Some(0xFD),
+        '█' => Some(0xFF),
+        '°' => Some(0xDF),
+        _ => None,
+    }
+}
+
+/// ASCII replacement of a character missing from the ROM, on one column.
+pub fn transliterate(c: char) -> Option<u8> {
+    Some(match c {
+        'à' | 'á' | 'â' | 'ã' | 'å' => b'a',
+        'À' | 'Á' | 'Â' | 'Ã' | 'Å' => b'A',
+        'Ä' => b'A',
+        'æ' => b'a',
+        'Æ' => b'A',
+        'ç' => b'c',
+        'Ç' => b'C',
+        'è' | 'é' | 'ê' | 'ë' => b'e',
+        'È' | 'É' | 'Ê' | 'Ë' => b'E',
+        'ì' | 'í' | 'î' | 'ï' => b'i',
+        'Ì' | 'Í' | 'Î' | 'Ï' => b'I',
+        'Ñ' => b'N',
+        'ò' | 'ó' | 'ô' | 'õ' | 'ø' => b'o',
+        'Ò' | 'Ó' | 'Ô' | 'Õ' | 'Ø' => b'O',
+        'Ö' => b'O',
+        'œ' => b'o',
+        'Œ' => b'O',
+        'ù' | 'ú' | 'û' => b'u',
+        'Ù' | 'Ú' | 'Û' => b'U',
+        'Ü' => b'U',
+        'ý' | 'ÿ' => b'y',
+        'Ý' | 'Ÿ' => b'Y',
+        '\\' => b'/',
+        '~' => b'-',
+        '‘' | '’' | '‚' | '′' => b'\'',
+        '“' | '”' | '„' | '«' | '»' | '″' => b'"',
+        '‹' => b'<',
+        '›' => b'>',
+        '–' | '—' | '‐' | '−' => b'-',
+        '…' => b'.',
+        '×' => b'x',
+        '€' => b'E',
+        '\u{a0}' | '\u{202f}' => b' ',
+        _ => return None,
+    })
+}
+
+/// Iterator over the ROM codes of a text.
+pub struct Encode<'a> {
+    chars: core::str::Chars<'a>,
+}
+
+impl Iterator for Encode<'_> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<u8> {
+        let c = self.chars.next()?;
+        Some(rom_code(c).or_else(|| transliterate(c)).unwrap_or(UNKNOWN))
+    }
+}
+
+/// Encodes the text into ROM codes, one per character.
+pub fn encode(text: &str) -> Encode<'_> {
+    Encode {
+        chars: text.chars(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::charset::{encode, rom_code, transliterate};
+
+    fn encoded(text: &str) -> Vec<u8> {
+        encode(text).collect()
+    }
+
+    #[test]
+    fn ascii_is_unchanged() {
+        assert_eq!(encoded("Lundi 18:00 <3"), b"Lundi 18:00 <3");
+    }
+
+    #[test]
+    fn rom_symbols_are_used() {
+        assert_eq!(encoded("21°C"), [b'2', b'1', 0xDF, b'C']);
+        assert_eq!(encoded("Grüße"), [b'G', b'r', 0xF5, 0xE2, b'e']);
+        assert_eq!(encoded("→"), [0x7E]);
+    }
+
+    #[test]
+    fn french_text_is_transliterated() {
+        assert_eq!(encoded("Unité Heure ?"), b"Unite Heure ?");
+        assert_eq!(encoded("À bientôt"), b"A bientot");
+        assert_eq!(encoded("Sœur « Noël »"), b"Sour \" Noel \"");
+    }
+
+    #[test]
+    fn ascii_missing_from_the_rom() {
+        // 0x5C and 0x7E show a yen sign and an arrow
+        assert_eq!(rom_code('\\'), None);
+        assert_eq!(encoded("a\\b~c"), b"a/b-c");
+    }
+
+    #[test]
+    fn glyph_slots_and_unknown_characters() {
+        assert_eq!(encoded("\u{0}\u{7}"), [0, 7]);
+        assert_eq!(transliterate('🗑'), None);
+        assert_eq!(encoded("漢\n"), b"??");
+    }
+
+    #[test]
+    fn each_character_takes_one_column() {
+        let text = "Cæsar, Œuvre… « 21°C » ~ 漢";
+        assert_eq!(encode(text).count(), text.chars().count());
+    }
+}
@@ -0,0 +1,308 @@
+//! Idle screen cycling through the clock, the next alarm, the missed alarms,
+//! the state of the device and the one of its clock.
+
+use arrayvec::ArrayString;
+use clock::drift::DriftTracker;
+use clock::DateTime;
+use core::fmt::Write;
+use locale::{Text, LANGUAGE};
+
+use crate::format::countdown_string;
+use crate::rows::{WriteCurrentDayAndTime, WriteRows};
+use crate::TextDisplay;
+
+/// Time each page of the idle screen stays visible.
+pub const DEFAULT_PAGE_DURATION_MS: u64 = 4000;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Page {
+    Clock,
+    NextAlarm,
+    // Only shown while some alarm has been missed
+    Missed,
+    Status,
+    // Drift of the clock and last sync
+    Diagnostics,
+}
+
+impl Page {
+    fn next(self) -> Self {
+        match self {
+            Page::Clock => Page::NextAlarm,
+            Page::NextAlarm => Page::Missed,
+            Page::Missed => Page::Status,
+            Page::Status => Page::Diagnostics,
+            Page::Diagnostics => Page::Clock,
+        }
+    }
+}
+
+/// What the pages tell about the alarms.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct AlarmSummary {
+    /// Description of the next alarm and the seconds until it starts, 0 if it
+    /// already did.
+    pub next: Option<(ArrayString<16>, u32)>,
+    /// Description of the first missed alarm and the number of missed ones.
+    pub missed: Option<(ArrayString<16>, usize)>,
+    pub enabled: usize,
+    pub total: usize,
+}
+
+pub struct Dashboard {
+    page_duration_ms: u64,
+    page: Page,
+    page_started_ms: u64,
+}
+
+impl Dashboard {
+    pub fn new(page_duration_ms: u64, now_ms: u64) -> Self {
+        Self {
+            page_duration_ms,
+            page: Page::Clock,
+            page_started_ms: now_ms,
+        }
+    }
+
+    pub fn page_duration_ms(&self) -> u64 {
+        self.page_duration_ms
+    }
+
+    pub fn set_page_duration_ms(&mut self, page_duration_ms: u64) {
+        self.page_duration_ms = page_duration_ms;
+    }
+
+    pub fn page(&self) -> Page {
+        self.page
+    }
+
+    /// Starts over from the clock, e.g. after the screen has been used for
+    /// something else.
+    pub fn restart(&mut self, now_ms: u64) {
+        self.page = Page::Clock;
+        self.page_started_ms = now_ms;
+    }
+
+    /// Draws the current page, moving to the next one once its time is over.
+    /// `now` is the local time shown and `utc` the same time in UTC.
+    pub fn render<D: TextDisplay>(
+        &mut self,
+        lcd: &mut D,
+        now: &DateTime,
+        utc: &DateTime,
+        now_ms: u64,
+        alarms: &AlarmSummary,
+        drift: &DriftTracker,
+    ) -> Result<(), D::Error> {
+        if now_ms - self.page_started_ms >= self.page_duration_ms {
+            self.page = self.page.next();
+            self.page_started_ms = now_ms;
+        }
+        if self.page == Page::Missed && alarms.missed.is_none() {
+            self.page = self.page.next();
+        }
+
+        match self.page {
+            Page::Clock => lcd.write_current_date_and_time(now),
+            Page::NextAlarm => match &alarms.next {
+                Some((description, seconds)) => {
+                    let mut countdown = ArrayString::<32>::new();
+                    if *seconds == 0 {
+                        countdown.push_str(LANGUAGE.text(Text::Now));
+                    } else {
+                        write!(
+                            countdown,
+                            "{} {}",
+                            LANGUAGE.text(Text::NextIn),
+                            countdown_string(*seconds)
+                        )
+                        .unwrap();
+                    }
+                    lcd.write_rows(description.as_str(), countdown.as_str())
+                }
+                None => lcd.write_rows(LANGUAGE.text(Text::NoAlarm), ""),
+            },
+            Page::Missed => {
+                let (description, count) = alarms.missed.as_ref().unwrap();
+                let mut title = ArrayString::<32>::new();
+                write!(title, "{}", LANGUAGE.text(Text::Missed)).unwrap();
+                // The other missed alarms are counted
+                if *count > 1 {
+                    write!(title, " +{}", count - 1).unwrap();
+                }
+                lcd.write_rows(title.as_str(), description.as_str())
+            }
+            Page::Status => {
+                let mut enabled = ArrayString::<32>::new();
+                write!(
+                    enabled,
+                    "{} {}/{}",
+                    LANGUAGE.text(Text::Alarms),
+                    alarms.enabled,
+                    alarms.total
+                )
+                .unwrap();
+                let mut uptime = ArrayString::<32>::new();
+                write!(
+                    uptime,
+                    "{} {}",
+                    LANGUAGE.text(Text::Uptime),
+                    countdown_string((now_ms / 1000) as u32)
+                )
+                .unwrap();
+                lcd.write_rows(enabled.as_str(), uptime.as_str())
+            }
+            Page::Diagnostics => {
+                let mut estimate = ArrayString::<32>::new();
+                write!(estimate, "{} ", LANGUAGE.text(Text::Drift)).unwrap();
+                match drift.ppb() {
+                    Some(ppb) => {
+                        let sign = if ppb < 0 { '-' } else { '+' };
+                        let ppb = ppb.unsigned_abs();
+                        write!(estimate, "{}{}.{}ppm", sign, ppb / 1000, ppb % 1000 / 100).unwrap()
+                    }
+                    None => estimate.push('?'),
+                }
+                let mut sync = ArrayString::<32>::new();
+                write!(sync, "{} ", LANGUAGE.text(Text::LastSync)).unwrap();
+                match drift.last_sync() {
+                    Some((time, _)) => {
+                        sync.push_str(&countdown_string(utc.timestamp().saturating_sub(time)))
+                    }
+                    None => sync.push('-'),
+                }
+                lcd.write_rows(estimate.as_str(), sync.as_str())
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use arrayvec::ArrayString;
+    use clock::drift::{DriftTracker, SyncSource};
+    use clock::DateTime;
+    use locale::{Text, LANGUAGE};
+
+    use crate::dashboard::{AlarmSummary, Dashboard, Page};
+    use crate::MemoryDisplay;
+
+    const NOON: DateTime = DateTime {
+        year: 2024,
+        month: 10,
+        day: 27,
+        hour: 12,
+        minute: 0,
+        second: 0,
+    };
+
+    fn rendered(
+        dashboard: &mut Dashboard,
+        now_ms: u64,
+        alarms: &AlarmSummary,
+        drift: &DriftTracker,
+    ) -> [String; 2] {
+        let mut display = MemoryDisplay::new();
+        dashboard
+            .render(&mut display, &NOON, &NOON, now_ms, alarms, drift)
+            .unwrap();
+        [0, 1].map(|row| display.row(row).trim_end().to_string())
+    }
+
+    fn summary() -> AlarmSummary {
+        AlarmSummary {
+            next: Some((ArrayString::from("Verre").unwrap(), 90 * 60)),
+            missed: None,
+            enabled: 2,
+            total: 3,
+        }
+    }
+
+    #[test]
+    fn pages_follow_each_other() {
+        let mut dashboard = Dashboard::new(4000, 0);
+        let drift = DriftTracker::new(None);
+
+        rendered(&mut dashboard, 3999, &summary(), &drift);
+        assert_eq!(dashboard.page(), Page::Clock);
+        assert_eq!(
+            rendered(&mut dashboard, 4000, &summary(), &drift),
+            [
+                "Verre".to_string(),
+                format!("{} 1h 30min", LANGUAGE.text(Text::NextIn))
+            ]
+        );
+        // Nothing was missed
+        assert_eq!(
+            rendered(&mut dashboard, 8000, &summary(), &drift),
+            [
+                format!("{} 2/3", LANGUAGE.text(Text::Alarms)),
+                format!("{} 0h 00min", LANGUAGE.text(Text::Uptime))
+            ]
+        );
+        assert_eq!(dashboard.page(), Page::Status);
+
+        dashboard.restart(9000);
+        assert_eq!(dashboard.page(), Page::Clock);
+    }
+
+    #[test]
+    fn missed_alarms_are_counted() {
+        let mut dashboard = Dashboard::new(4000, 0);
+        let drift = DriftTracker::new(None);
+        let alarms = AlarmSummary {
+            missed: Some((ArrayString::from("Compost").unwrap(), 3)),
+            ..summary()
+        };
+
+        rendered(&mut dashboard, 4000, &alarms, &drift);
+
+        assert_eq!(
+            rendered(&mut dashboard, 8000, &alarms, &drift),
+            [
+                format!("{} +2", LANGUAGE.text(Text::Missed)),
+                "Compost".to_string()
+            ]
+        );
+    }
+
+    #[test]
+    fn diagnostics_show_the_drift_and_last_sync() {
+        let mut dashboard = Dashboard::new(4000, 0);
+        let mut drift = DriftTracker::new(Some(-46_296));
+        let sync = NOON.timestamp() - 2 * 3600 - 5 * 60;
+        drift.record(SyncSource::Radio, sync, Some(sync + 1));
+        for now_ms in [4000, 8000] {
+            rendered(&mut dashboard, now_ms, &summary(), &drift);
+        }
+
+        assert_eq!(
+            rendered(&mut dashboard, 12000, &summary(), &drift),
+            [
+                format!("{} -46.2ppm", LANGUAGE.text(Text::Drift)),
+                format!("{} 2h 05min", LANGUAGE.text(Text::LastSync))
+            ]
+        );
+        assert_eq!(dashboard.page(), Page::Diagnostics);
+    }
+
+    #[test]
+    fn unknown_drift_and_no_alarm() {
+        let mut dashboard = Dashboard::new(4000, 0);
+        let drift = DriftTracker::new(None);
+        let alarms = AlarmSummary::default();
+
+        assert_eq!(
+            rendered(&mut dashboard, 4000, &alarms, &drift),
+            [LANGUAGE.text(Text::NoAlarm).to_string(), String::new()]
+        );
+        rendered(&mut dashboard, 8000, &alarms, &drift);
+        assert_eq!(
+            rendered(&mut dashboard, 12000, &alarms, &drift),
+            [
+                format!("{} ?", LANGUAGE.text(Text::Drift)),
+                format!("{} -", LANGUAGE.text(Text::LastSync))
+            ]
+        );
+    }
+}
@@ -0,0 +1,89 @@
+//! Dates and durations written in the language of the firmware.
+
+use arrayvec::ArrayString;
+use clock::DateTime;
+use core::fmt::Write;
+use locale::{Text, LANGUAGE, WEEKDAY_COLUMNS};
+
+/// Name of the day of the week, 0 being Sunday, padded to the longest one.
+pub fn day_of_week_string(day_of_week: u8) -> ArrayString<16> {
+    let mut text = ArrayString::new();
+    write!(
+        text,
+        "{: <1$}",
+        LANGUAGE.weekday(day_of_week),
+        WEEKDAY_COLUMNS
+    )
+    .unwrap();
+    text
+}
+
+/// Duration such as "1j 04h", or "4h 05min" under a day.
+pub fn countdown_string(seconds: u32) -> ArrayString<16> {
+    let (days, hours, minutes) = (seconds / 86400, seconds / 3600 % 24, seconds / 60 % 60);
+
+    let mut text = ArrayString::new();
+    if days > 0 {
+        let day_unit = LANGUAGE.text(Text::DayUnit);
+        write!(text, "{}{} {:0>2}h", days, day_unit, hours).unwrap();
+    } else {
+        write!(text, "{}h {:0>2}min", hours, minutes).unwrap();
+    }
+    text
+}
+
+/// Time such as "18:05:00".
+pub fn time_string(datetime: &DateTime) -> ArrayString<8> {
+    let mut text = ArrayString::new();
+    write!(
+        text,
+        "{:0>2}:{:0>2}:{:0>2}",
+        datetime.hour, datetime.minute, datetime.second
+    )
+    .unwrap();
+    text
+}
+
+/// Day and month such as "24/12".
+pub fn short_date_string(datetime: &DateTime) -> ArrayString<5> {
+    let mut text = ArrayString::new();
+    write!(text, "{:0>2}/{:0>2}", datetime.day, datetime.month).unwrap();
+    text
+}
+
+#[cfg(test)]
+mod tests {
+    use clock::DateTime;
+    use locale::{Text, LANGUAGE, WEEKDAY_COLUMNS};
+
+    use crate::format::{countdown_string, day_of_week_string, short_date_string, time_string};
+
+    #[test]
+    fn countdowns_switch_to_days() {
+        assert_eq!(
+            countdown_string(4 * 3600 + 5 * 60 + 59).as_str(),
+            "4h 05min"
+        );
+        assert_eq!(
+            countdown_string(6 * 86400 + 23 * 3600).as_str(),
+            format!("6{} 23h", LANGUAGE.text(Text::DayUnit))
+        );
+    }
+
+    #[test]
+    fn dates_and_times_are_zero_padded() {
+        let datetime = DateTime {
+            year: 2024,
+            month: 3,
+            day: 7,
+            hour: 8,
+            minute: 5,
+            second: 0,
+        };
+
+        assert_eq!(time_string(&datetime).as_str(), "08:05:00");
+        assert_eq!(short_date_string(&datetime).as_str(), "07/03");
+        assert_eq!(day_of_week_string(4).chars().count(), WEEKDAY_COLUMNS);
+        assert!(day_of_week_string(4).starts_with(LANGUAGE.weekday(4)));
+    }
+}
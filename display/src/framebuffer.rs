@@ -0,0 +1,411 @@
+//! Shadow copy of the display content.
+//!
+//! Screens are drawn into a `FrameBuffer` instead of the LCD. `flush` then
+//! compares it with what the LCD shows and only sends the cells, colour and
+//! cursor state that changed, so redrawing a whole screen at every loop does
+//! not flicker nor saturate the I²C bus.
+
+use arrayvec::ArrayString;
+use core::convert::Infallible;
+
+use crate::glyphs::CGRAM_SLOTS;
+use crate::{TextDisplay, COLUMNS, ROWS};
+
+type Cells = [[char; COLUMNS]; ROWS];
+
+// State of the LCD after the last flush.
+#[derive(Clone, Copy, Debug, PartialEq)]
+struct Shown {
+    cells: [[Option<char>; COLUMNS]; ROWS],
+    rgb: Option<(u8, u8, u8)>,
+    blink: Option<bool>,
+    cursor: Option<(u8, u8)>,
+}
+
+impl Shown {
+    // Nothing is known about the LCD.
+    const UNKNOWN: Shown = Shown {
+        cells: [[None; COLUMNS]; ROWS],
+        rgb: None,
+        blink: None,
+        cursor: None,
+    };
+}
+
+/// In-memory `TextDisplay` sending only its differences to a real display.
+#[derive(Debug)]
+pub struct FrameBuffer {
+    cells: Cells,
+    cursor: (u8, u8),
+    blink: bool,
+    rgb: (u8, u8, u8),
+    glyphs: [Option<[u8; 8]>; CGRAM_SLOTS],
+    shown: Shown,
+}
+
+impl FrameBuffer {
+    pub fn new() -> Self {
+        Self {
+            cells: [[' '; COLUMNS]; ROWS],
+            cursor: (0, 0),
+            blink: false,
+            rgb: (0, 0, 0),
+            glyphs: [None; CGRAM_SLOTS],
+            shown: Shown::UNKNOWN,
+        }
+    }
+
+    /// Forgets what the display shows, the next flush redraws everything.
+    /// Needed after something else wrote to the display.
+    pub fn invalidate(&mut self) {
+        self.shown = Shown::UNKNOWN;
+    }
+
+    /// Sends the changes since the last flush to `display`.
+    pub fn flush<D: TextDisplay>(&mut self, display: &mut D) -> Result<(), D::Error> {
+        for slot in 0..CGRAM_SLOTS {
+            if let Some(bitmap) = self.glyphs[slot] {
+                // The cursor now points to the glyph memory.
+                self.shown.cursor = None;
+                display.upload_glyph(slot as u8, &bitmap)?;
+                self.glyphs[slot] = None;
+            }
+        }
+        if self.shown.rgb != Some(self.rgb) {
+            display.set_rgb(self.rgb.0, self.rgb.1, self.rgb.2)?;
+            self.shown.rgb = Some(self.rgb);
+        }
+        for row in 0..ROWS {
+            let mut column = 0;
+            while column < COLUMNS {
+                if self.shown.cells[row][column] == Some(self.cells[row][column]) {
+                    column += 1;
+                    continue;
+                }
+                // Send the run of changed cells in one write.
+                let start = column;
+                let mut run = ArrayString::<{ COLUMNS * 4 }>::new();
+                while column < COLUMNS
+                    && self.shown.cells[row][column] != Some(self.cells[row][column])
+                {
+                    run.push(self.cells[row][column]);
+                    column += 1;
+                }
+                let position = (start as u8, row as u8);
+                // Until the run is written, which the next flush retries if
+                // it fails, the cells and cursor are unknown.
+                if self.shown.cursor.take() != Some(position) {
+                    display.set_cursor_position(position.0, position.1)?;
+                }
+                display.write_str(run.as_str())?;
+                for cell in start..column {
+                    self.shown.cells[row][cell] = Some(self.cells[row][cell]);
+                }
+                self.shown.cursor = Some((column as u8, row as u8));
+            }
+        }
+        if self.shown.blink != Some(self.blink) {
+            display.set_blink(self.blink)?;
+            self.shown.blink = Some(self.blink);
+        }
+        // The cursor only matters when it blinks.
+        if self.blink && self.shown.cursor != Some(self.cursor) {
+            self.shown.cursor = None;
+            display.set_cursor_position(self.cursor.0, self.cursor.1)?;
+            self.shown.cursor = Some(self.cursor);
+        }
+        Ok(())
+    }
+}
+
+impl Default for FrameBuffer {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextDisplay for FrameBuffer {
+    type Error = Infallible;
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.cells = [[' '; COLUMNS]; ROWS];
+        self.cursor = (0, 0);
+        Ok(())
+    }
+
+    fn set_cursor_position(&mut self, column: u8, row: u8) -> Result<(), Self::Error> {
+        self.cursor = (column, if row == 0 { 0 } else { 1 });
+        Ok(())
+    }
+
+    fn set_blink(&mut self, blink: bool) -> Result<(), Self::Error> {
+        self.blink = blink;
+        Ok(())
+    }
+
+    fn write_str(&mut self, text: &str) -> Result<(), Self::Error> {
+        for c in text.chars() {
+            let (column, row) = self.cursor;
+            if (column as usize) < COLUMNS {
+                self.cells[row as usize][column as usize] = c;
+            }
+            self.cursor.0 = column.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    fn set_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), Self::Error> {
+        self.rgb = (r, g, b);
+        Ok(())
+    }
+
+    fn upload_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<(), Self::Error> {
+        self.glyphs[slot as usize % CGRAM_SLOTS] = Some(*bitmap);
+        // Cells showing the slot change on the display without being written.
+        for row in self.shown.cells.iter_mut() {
+            for cell in row
+                .iter_mut()
+                .filter(|cell| **cell == Some(char::from(slot)))
+            {
+                *cell = None;
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::framebuffer::FrameBuffer;
+    use crate::TextDisplay;
+
+    #[derive(Debug, PartialEq)]
+    enum Command {
+        Clear,
+        Cursor(u8, u8),
+        Blink(bool),
+        Write(String),
+        Rgb(u8, u8, u8),
+        Glyph(u8),
+    }
+
+    #[derive(Default)]
+    struct RecordingDisplay {
+        commands: Vec<Command>,
+        // Writes and glyph uploads fail, like on a NACK
+        failing: bool,
+    }
+
+    impl TextDisplay for RecordingDisplay {
+        type Error = ();
+
+        fn clear(&mut self) -> Result<(), Self::Error> {
+            self.commands.push(Command::Clear);
+            Ok(())
+        }
+
+        fn set_cursor_position(&mut self, column: u8, row: u8) -> Result<(), Self::Error> {
+            self.commands.push(Command::Cursor(column, row));
+            Ok(())
+        }
+
+        fn set_blink(&mut self, blink: bool) -> Result<(), Self::Error> {
+            self.commands.push(Command::Blink(blink));
+            Ok(())
+        }
+
+        fn write_str(&mut self, text: &str) -> Result<(), Self::Error> {
+            if self.failing {
+                return Err(());
+            }
+            self.commands.push(Command::Write(text.to_string()));
+            Ok(())
+        }
+
+        fn set_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), Self::Error> {
+            self.commands.push(Command::Rgb(r, g, b));
+            Ok(())
+        }
+
+        fn upload_glyph(&mut self, slot: u8, _bitmap: &[u8; 8]) -> Result<(), Self::Error> {
+            if self.failing {
+                return Err(());
+            }
+            self.commands.push(Command::Glyph(slot));
+            Ok(())
+        }
+    }
+
+    fn draw_time(frame: &mut FrameBuffer, time: &str) {
+        frame.clear().unwrap();
+        frame.write_str("Mercredi").unwrap();
+        frame.set_cursor_position(0, 1).unwrap();
+        frame.write_str(time).unwrap();
+    }
+
+    fn flushed(frame: &mut FrameBuffer) -> Vec<Command> {
+        let mut display = RecordingDisplay::default();
+        frame.flush(&mut display).unwrap();
+        display.commands
+    }
+
+    #[test]
+    fn first_flush_draws_everything() {
+        let mut frame = FrameBuffer::new();
+        draw_time(&mut frame, "18:00:00");
+
+        assert_eq!(
+            flushed(&mut frame),
+            vec![
+                Command::Rgb(0, 0, 0),
+                Command::Cursor(0, 0),
+                Command::Write("Mercredi        ".to_string()),
+                Command::Cursor(0, 1),
+                Command::Write("18:00:00        ".to_string()),
+                Command::Blink(false),
+            ]
+        );
+    }
+
+    #[test]
+    fn unchanged_screen_sends_nothing() {
+        let mut frame = FrameBuffer::new();
+        draw_time(&mut frame, "18:00:00");
+        flushed(&mut frame);
+
+        draw_time(&mut frame, "18:00:00");
+
+        assert_eq!(flushed(&mut frame), vec![]);
+    }
+
+    #[test]
+    fn only_changed_cells_are_written() {
+        let mut frame = FrameBuffer::new();
+        draw_time(&mut frame, "18:09:59");
+        flushed(&mut frame);
+
+        draw_time(&mut frame, "18:10:00");
+
+        assert_eq!(
+            flushed(&mut frame),
+            vec![
+                Command::Cursor(3, 1),
+                Command::Write("10".to_string()),
+                Command::Cursor(6, 1),
+                Command::Write("00".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn consecutive_runs_skip_the_cursor_move() {
+        let mut frame = FrameBuffer::new();
+        draw_time(&mut frame, "");
+        flushed(&mut frame);
+
+        frame.set_cursor_position(14, 0).unwrap();
+        frame.write_str("!!").unwrap();
+        frame.set_cursor_position(0, 1).unwrap();
+        frame.write_str("?").unwrap();
+
+        assert_eq!(
+            flushed(&mut frame),
+            vec![
+                Command::Cursor(14, 0),
+                Command::Write("!!".to_string()),
+                Command::Cursor(0, 1),
+                Command::Write("?".to_string()),
+            ]
+        );
+        frame.set_cursor_position(1, 1).unwrap();
+        frame.write_str("ab").unwrap();
+        frame.set_cursor_position(4, 1).unwrap();
+        frame.write_str("c").unwrap();
+        assert_eq!(
+            flushed(&mut frame),
+            vec![
+                // The cursor is already after the `?`
+                Command::Write("ab".to_string()),
+                Command::Cursor(4, 1),
+                Command::Write("c".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn colour_and_blinking_cursor() {
+        let mut frame = FrameBuffer::new();
+        draw_time(&mut frame, "18:00");
+        flushed(&mut frame);
+
+        frame.set_rgb(0, 255, 0).unwrap();
+        frame.set_blink(true).unwrap();
+        frame.set_cursor_position(1, 1).unwrap();
+
+        assert_eq!(
+            flushed(&mut frame),
+            vec![
+                Command::Rgb(0, 255, 0),
+                Command::Blink(true),
+                Command::Cursor(1, 1),
+            ]
+        );
+        assert_eq!(flushed(&mut frame), vec![]);
+    }
+
+    #[test]
+    fn invalidate_redraws_everything() {
+        let mut frame = FrameBuffer::new();
+        draw_time(&mut frame, "18:00");
+        flushed(&mut frame);
+
+        frame.invalidate();
+
+        assert_eq!(flushed(&mut frame).len(), 6);
+    }
+
+    #[test]
+    fn glyph_upload_rewrites_the_cells_using_it() {
+        let mut frame = FrameBuffer::new();
+        frame.write_str("a\u{0}b").unwrap();
+        flushed(&mut frame);
+
+        frame.upload_glyph(0, &[0; 8]).unwrap();
+
+        assert_eq!(
+            flushed(&mut frame),
+            vec![
+                Command::Glyph(0),
+                Command::Cursor(1, 0),
+                Command::Write("\u{0}".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn failed_writes_are_retried() {
+        let mut frame = FrameBuffer::new();
+        draw_time(&mut frame, "18:09:59");
+        flushed(&mut frame);
+        let mut failing = RecordingDisplay {
+            failing: true,
+            ..RecordingDisplay::default()
+        };
+
+        draw_time(&mut frame, "18:10:00");
+        assert_eq!(frame.flush(&mut failing), Err(()));
+
+        assert_eq!(
+            flushed(&mut frame),
+            vec![
+                Command::Cursor(3, 1),
+                Command::Write("10".to_string()),
+                Command::Cursor(6, 1),
+                Command::Write("00".to_string()),
+            ]
+        );
+        frame.upload_glyph(0, &[0; 8]).unwrap();
+        assert_eq!(frame.flush(&mut failing), Err(()));
+        assert_eq!(flushed(&mut frame), vec![Command::Glyph(0)]);
+    }
+}
@@ -0,0 +1,443 @@
+//! Custom characters of the HD44780 character generator RAM.
+//!
+//! The controller has 8 user-definable characters, shown by writing the codes
+//! 0 to 7. `GlyphDisplay` lets the rest of the code write the real characters
+//! (`é`, `♥`, ...): it uploads their bitmaps on first use and reuses the least
+//! recently used slot that is not on screen once all of them are taken.
+
+use arrayvec::ArrayString;
+
+use crate::{TextDisplay, COLUMNS, ROWS};
+
+/// Number of user-definable characters of the controller.
+pub const CGRAM_SLOTS: usize = 8;
+
+/// A character drawn in a 5x8 pixel cell.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Glyph {
+    pub character: char,
+    /// Written instead when no slot is left.
+    pub fallback: char,
+    /// Pixel rows from top to bottom, the 5 lowest bits of each byte are used.
+    pub bitmap: [u8; 8],
+}
+
+pub const BIN: Glyph = Glyph {
+    character: '🗑',
+    fallback: '#',
+    bitmap: [
+        0b01110, 0b11111, 0b10001, 0b10101, 0b10101, 0b10101, 0b10001, 0b01110,
+    ],
+};
+
+pub const BELL: Glyph = Glyph {
+    character: '🔔',
+    fallback: '!',
+    bitmap: [
+        0b00100, 0b01110, 0b01110, 0b01110, 0b11111, 0b00000, 0b00100, 0b00000,
+    ],
+};
+
+pub const HEART: Glyph = Glyph {
+    character: '♥',
+    fallback: '*',
+    bitmap: [
+        0b00000, 0b01010, 0b11111, 0b11111, 0b01110, 0b00100, 0b00000, 0b00000,
+    ],
+};
+
+pub const E_ACUTE: Glyph = Glyph {
+    character: 'é',
+    fallback: 'e',
+    bitmap: [
+        0b00010, 0b00100, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110, 0b00000,
+    ],
+};
+
+pub const E_GRAVE: Glyph = Glyph {
+    character: 'è',
+    fallback: 'e',
+    bitmap: [
+        0b01000, 0b00100, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110, 0b00000,
+    ],
+};
+
+pub const E_CIRCUMFLEX: Glyph = Glyph {
+    character: 'ê',
+    fallback: 'e',
+    bitmap: [
+        0b00100, 0b01010, 0b01110, 0b10001, 0b11111, 0b10000, 0b01110, 0b00000,
+    ],
+};
+
+pub const A_GRAVE: Glyph = Glyph {
+    character: 'à',
+    fallback: 'a',
+    bitmap: [
+        0b01000, 0b00100, 0b01110, 0b00001, 0b01111, 0b10001, 0b01111, 0b00000,
+    ],
+};
+
+pub const U_GRAVE: Glyph = Glyph {
+    character: 'ù',
+    fallback: 'u',
+    bitmap: [
+        0b01000, 0b00100, 0b10001, 0b10001, 0b10001, 0b10011, 0b01101, 0b00000,
+    ],
+};
+
+pub const C_CEDILLA: Glyph = Glyph {
+    character: 'ç',
+    fallback: 'c',
+    bitmap: [
+        0b00000, 0b01110, 0b10000, 0b10000, 0b10001, 0b01110, 0b00100, 0b01100,
+    ],
+};
+
+pub const CAPITAL_E_ACUTE: Glyph = Glyph {
+    character: 'É',
+    fallback: 'E',
+    bitmap: [
+        0b00010, 0b00100, 0b11111, 0b10000, 0b11110, 0b10000, 0b11111, 0b00000,
+    ],
+};
+
+/// Every glyph known by the firmware.
+pub const GLYPHS: &[Glyph] = &[
+    BIN,
+    BELL,
+    HEART,
+    E_ACUTE,
+    E_GRAVE,
+    E_CIRCUMFLEX,
+    A_GRAVE,
+    U_GRAVE,
+    C_CEDILLA,
+    CAPITAL_E_ACUTE,
+];
+
+/// Slot given to a glyph by `GlyphSlots::assign`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Assignment {
+    pub slot: u8,
+    /// The slot held another glyph, or nothing, and must be uploaded.
+    pub upload: bool,
+}
+
+/// Bookkeeping of which glyph is in which slot, with least recently used eviction.
+#[derive(Debug)]
+pub struct GlyphSlots {
+    slots: [Option<char>; CGRAM_SLOTS],
+    last_used: [u32; CGRAM_SLOTS],
+    clock: u32,
+    // Number of cells of the screen showing each slot
+    visible: [u8; CGRAM_SLOTS],
+}
+
+impl GlyphSlots {
+    pub fn new() -> Self {
+        Self {
+            slots: [None; CGRAM_SLOTS],
+            last_used: [0; CGRAM_SLOTS],
+            clock: 0,
+            visible: [0; CGRAM_SLOTS],
+        }
+    }
+
+    /// Returns the slot holding `character`, taking a free or the least
+    /// recently used one if needed. Slots shown on screen are never taken, as
+    /// uploading another glyph would change the cells showing them; `None` is
+    /// returned when all of them are.
+    pub fn assign(&mut self, character: char) -> Option<Assignment> {
+        self.clock += 1;
+        if let Some(slot) = self.slots.iter().position(|&c| c == Some(character)) {
+            self.last_used[slot] = self.clock;
+            return Some(Assignment {
+                slot: slot as u8,
+                upload: false,
+            });
+        }
+        // Free slots are taken before the least recently used ones
+        let slot = (0..CGRAM_SLOTS)
+            .filter(|&slot| self.visible[slot] == 0)
+            .min_by_key(|&slot| (self.slots[slot].is_some(), self.last_used[slot]))?;
+        self.slots[slot] = Some(character);
+        self.last_used[slot] = self.clock;
+        Some(Assignment {
+            slot: slot as u8,
+            upload: true,
+        })
+    }
+
+    /// Records that a cell of the screen now shows the slot.
+    pub fn show(&mut self, slot: u8) {
+        let visible = &mut self.visible[slot as usize % CGRAM_SLOTS];
+        *visible = visible.saturating_add(1);
+    }
+
+    /// Records that a cell showing the slot was overwritten.
+    pub fn hide(&mut self, slot: u8) {
+        let visible = &mut self.visible[slot as usize % CGRAM_SLOTS];
+        *visible = visible.saturating_sub(1);
+    }
+
+    /// Records that the screen was cleared.
+    pub fn hide_all(&mut self) {
+        self.visible = [0; CGRAM_SLOTS];
+    }
+
+    /// Marks the slot as free, e.g. after it was overwritten directly.
+    pub fn forget(&mut self, slot: u8) {
+        self.slots[slot as usize % CGRAM_SLOTS] = None;
+    }
+
+    pub fn slot_of(&self, character: char) -> Option<u8> {
+        self.slots
+            .iter()
+            .position(|&c| c == Some(character))
+            .map(|slot| slot as u8)
+    }
+}
+
+impl Default for GlyphSlots {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// `TextDisplay` showing the glyphs of `glyphs` through the custom characters of `display`.
+pub struct GlyphDisplay<D: TextDisplay> {
+    display: D,
+    glyphs: &'static [Glyph],
+    slots: GlyphSlots,
+    cursor: (u8, u8),
+    // Slot shown by each cell, if any
+    cells: [[Option<u8>; COLUMNS]; ROWS],
+}
+
+impl<D: TextDisplay> GlyphDisplay<D> {
+    pub fn new(display: D, glyphs: &'static [Glyph]) -> Self {
+        Self {
+            display,
+            glyphs,
+            slots: GlyphSlots::new(),
+            cursor: (0, 0),
+            cells: [[None; COLUMNS]; ROWS],
+        }
+    }
+
+    pub fn inner(&self) -> &D {
+        &self.display
+    }
+
+    pub fn inner_mut(&mut self) -> &mut D {
+        &mut self.display
+    }
+
+    fn glyph(&self, c: char) -> Option<&'static Glyph> {
+        self.glyphs.iter().find(|glyph| glyph.character == c)
+    }
+
+    // Updates the slots on screen for the character written at the cursor.
+    fn track(&mut self, c: char) {
+        let (column, row) = self.cursor;
+        let cell = match self.cells.get_mut(row as usize) {
+            Some(cells) => match cells.get_mut(column as usize) {
+                Some(cell) => cell,
+                None => return,
+            },
+            None => return,
+        };
+        if let Some(slot) = cell.take() {
+            self.slots.hide(slot);
+        }
+        if (c as u32) < CGRAM_SLOTS as u32 {
+            *cell = Some(c as u8);
+            self.slots.show(c as u8);
+        }
+    }
+
+    fn flush(&mut self, pending: &mut ArrayString<32>) -> Result<(), D::Error> {
+        if !pending.is_empty() {
+            self.display.write_str(pending.as_str())?;
+            pending.clear();
+        }
+        Ok(())
+    }
+}
+
+impl<D: TextDisplay> TextDisplay for GlyphDisplay<D> {
+    type Error = D::Error;
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        // Clearing the display keeps the character generator RAM.
+        self.cursor = (0, 0);
+        self.cells = [[None; COLUMNS]; ROWS];
+        self.slots.hide_all();
+        self.display.clear()
+    }
+
+    fn set_cursor_position(&mut self, column: u8, row: u8) -> Result<(), Self::Error> {
+        self.cursor = (column, row);
+        self.display.set_cursor_position(column, row)
+    }
+
+    fn set_blink(&mut self, blink: bool) -> Result<(), Self::Error> {
+        self.display.set_blink(blink)
+    }
+
+    fn write_str(&mut self, text: &str) -> Result<(), Self::Error> {
+        let mut pending = ArrayString::<32>::new();
+        for c in text.chars() {
+            // The overwritten cell no longer needs its slot
+            self.track(' ');
+            let c = match self.glyph(c) {
+                Some(glyph) => match self.slots.assign(c) {
+                    Some(assignment) => {
+                        if assignment.upload {
+                            self.flush(&mut pending)?;
+                            self.display.upload_glyph(assignment.slot, &glyph.bitmap)?;
+                            let (column, row) = self.cursor;
+                            self.display.set_cursor_position(column, row)?;
+                        }
+                        char::from(assignment.slot)
+                    }
+                    None => glyph.fallback,
+                },
+                None => c,
+            };
+            self.track(c);
+            if pending.try_push(c).is_err() {
+                self.flush(&mut pending)?;
+                pending.push(c);
+            }
+            self.cursor.0 = self.cursor.0.saturating_add(1);
+        }
+        self.flush(&mut pending)
+    }
+
+    fn set_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), Self::Error> {
+        self.display.set_rgb(r, g, b)
+    }
+
+    fn upload_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<(), Self::Error> {
+        // The slot no longer holds a known glyph.
+        self.slots.forget(slot);
+        self.display.upload_glyph(slot, bitmap)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::glyphs::{
+        GlyphDisplay, GlyphSlots, A_GRAVE, BELL, BIN, CAPITAL_E_ACUTE, C_CEDILLA, E_ACUTE, E_GRAVE,
+        GLYPHS, HEART,
+    };
+    use crate::{MemoryDisplay, TextDisplay};
+
+    #[test]
+    fn glyphs_take_free_slots_once() {
+        let mut display = GlyphDisplay::new(MemoryDisplay::new(), GLYPHS);
+
+        display.write_str("Merci ♥ ♥").unwrap();
+
+        let memory = display.inner();
+        assert_eq!(memory.row(0).as_str(), "Merci \u{0} \u{0}       ");
+        assert_eq!(memory.glyph(0), HEART.bitmap);
+        assert_eq!(memory.glyph_uploads(), 1);
+    }
+
+    #[test]
+    fn cursor_is_restored_after_an_upload() {
+        let mut display = GlyphDisplay::new(MemoryDisplay::new(), GLYPHS);
+
+        display.set_cursor_position(2, 1).unwrap();
+        display.write_str("Réveil à 6h").unwrap();
+
+        let memory = display.inner();
+        assert_eq!(memory.row(1).as_str(), "  R\u{0}veil \u{1} 6h   ");
+        assert_eq!(memory.glyph(0), E_ACUTE.bitmap);
+        assert_eq!(memory.glyph(1), A_GRAVE.bitmap);
+        assert_eq!(memory.cursor_position(), (13, 1));
+    }
+
+    #[test]
+    fn uploaded_glyphs_survive_a_clear() {
+        let mut display = GlyphDisplay::new(MemoryDisplay::new(), GLYPHS);
+        display.write_str("🗑 🔔").unwrap();
+
+        display.clear().unwrap();
+        display.write_str("🔔 🗑").unwrap();
+
+        assert_eq!(display.inner().row(0).as_str(), "\u{1} \u{0}             ");
+        assert_eq!(display.inner().glyph_uploads(), 2);
+    }
+
+    #[test]
+    fn least_recently_used_slot_is_reused() {
+        let mut slots = GlyphSlots::new();
+        for c in "abcdefgh".chars() {
+            assert!(slots.assign(c).unwrap().upload);
+        }
+        // `a` is used again, `b` becomes the oldest
+        slots.assign('a');
+
+        let assignment = slots.assign('z').unwrap();
+
+        assert!(assignment.upload);
+        assert_eq!(Some(assignment.slot), slots.slot_of('z'));
+        assert_eq!(slots.slot_of('b'), None);
+        assert_eq!(slots.slot_of('a'), Some(0));
+    }
+
+    #[test]
+    fn fallback_when_a_write_needs_more_than_eight_glyphs() {
+        let mut display = GlyphDisplay::new(MemoryDisplay::new(), GLYPHS);
+
+        display.write_str("🗑🔔♥éèêàùçÉ").unwrap();
+
+        assert_eq!(
+            display.inner().row(0).as_str(),
+            "\u{0}\u{1}\u{2}\u{3}\u{4}\u{5}\u{6}\u{7}cE      "
+        );
+        assert_eq!(display.inner().glyph(0), BIN.bitmap);
+        assert_eq!(display.inner().glyph(1), BELL.bitmap);
+
+        // The next write may reuse the slots
+        display.clear().unwrap();
+        display.write_str("É").unwrap();
+        assert_eq!(display.inner().row(0).as_str(), "\u{0}               ");
+        assert_eq!(display.inner().glyph(0), CAPITAL_E_ACUTE.bitmap);
+        assert_eq!(display.inner().glyph(4), E_GRAVE.bitmap);
+    }
+
+    #[test]
+    fn glyphs_on_screen_are_not_evicted() {
+        let mut display = GlyphDisplay::new(MemoryDisplay::new(), GLYPHS);
+
+        display.write_str("🗑🔔♥éè").unwrap();
+        display.set_cursor_position(0, 1).unwrap();
+        display.write_str("êàùçÉ").unwrap();
+
+        let memory = display.inner();
+        assert_eq!(
+            memory.row(0).as_str(),
+            "\u{0}\u{1}\u{2}\u{3}\u{4}           "
+        );
+        assert_eq!(memory.row(1).as_str(), "\u{5}\u{6}\u{7}cE           ");
+        assert_eq!(memory.glyph(0), BIN.bitmap);
+
+        // Overwritten cells free their slots
+        display.set_cursor_position(0, 0).unwrap();
+        display.write_str("Lundi").unwrap();
+        display.set_cursor_position(3, 1).unwrap();
+        display.write_str("çÉ").unwrap();
+        let memory = display.inner();
+        assert_eq!(
+            memory.row(1).as_str(),
+            "\u{5}\u{6}\u{7}\u{0}\u{1}           "
+        );
+        assert_eq!(memory.glyph(0), C_CEDILLA.bitmap);
+        assert_eq!(memory.glyph(1), CAPITAL_E_ACUTE.bitmap);
+    }
+}
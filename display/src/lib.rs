@@ -0,0 +1,44 @@
+#![cfg_attr(not(test), no_std)]
+
+pub mod backlight;
+pub mod charset;
+pub mod dashboard;
+pub mod format;
+pub mod framebuffer;
+pub mod glyphs;
+pub mod memory;
+pub mod message;
+pub mod power;
+pub mod rows;
+
+pub use framebuffer::FrameBuffer;
+pub use glyphs::GlyphDisplay;
+pub use memory::MemoryDisplay;
+pub use message::{show_message, show_ringing_message};
+
+use arrayvec::ArrayString;
+
+/// Number of visible columns of the 1602 display.
+pub const COLUMNS: usize = 16;
+/// Number of rows of the 1602 display.
+pub const ROWS: usize = 2;
+
+/// Maximum length in bytes of a message shown with `show_message`.
+pub const MESSAGE_CAPACITY: usize = 64;
+pub type Message = ArrayString<MESSAGE_CAPACITY>;
+
+/// Character display with an RGB backlight.
+pub trait TextDisplay {
+    type Error;
+
+    fn clear(&mut self) -> Result<(), Self::Error>;
+    fn set_cursor_position(&mut self, column: u8, row: u8) -> Result<(), Self::Error>;
+    // Turns the blinking block cursor on or off
+    fn set_blink(&mut self, blink: bool) -> Result<(), Self::Error>;
+    // Writes the text at the cursor position, the cursor advances after each character
+    fn write_str(&mut self, text: &str) -> Result<(), Self::Error>;
+    fn set_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), Self::Error>;
+    // Defines the 5x8 pixels shown for the character code `slot` (0 to 7), one byte per pixel row.
+    // The cursor position is lost and must be set again before writing.
+    fn upload_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<(), Self::Error>;
+}
@@ -0,0 +1,156 @@
+use arrayvec::ArrayString;
+use core::convert::Infallible;
+
+use crate::glyphs::CGRAM_SLOTS;
+use crate::{TextDisplay, COLUMNS, ROWS};
+
+/// `TextDisplay` keeping its content in memory, used to test display code on the host.
+pub struct MemoryDisplay {
+    cells: [[char; COLUMNS]; ROWS],
+    cursor: (u8, u8),
+    blink: bool,
+    rgb: (u8, u8, u8),
+    glyphs: [[u8; 8]; CGRAM_SLOTS],
+    uploads: usize,
+}
+
+impl MemoryDisplay {
+    pub fn new() -> Self {
+        Self {
+            cells: [[' '; COLUMNS]; ROWS],
+            cursor: (0, 0),
+            blink: false,
+            rgb: (0, 0, 0),
+            glyphs: [[0; 8]; CGRAM_SLOTS],
+            uploads: 0,
+        }
+    }
+
+    /// Visible content of the row, padded with spaces.
+    pub fn row(&self, row: usize) -> ArrayString<{ COLUMNS * 4 }> {
+        let mut text = ArrayString::new();
+        for c in self.cells[row] {
+            text.push(c);
+        }
+        text
+    }
+
+    pub fn cursor_position(&self) -> (u8, u8) {
+        self.cursor
+    }
+
+    pub fn is_blinking(&self) -> bool {
+        self.blink
+    }
+
+    pub fn rgb(&self) -> (u8, u8, u8) {
+        self.rgb
+    }
+
+    /// Bitmap last uploaded in the slot.
+    pub fn glyph(&self, slot: u8) -> [u8; 8] {
+        self.glyphs[slot as usize]
+    }
+
+    /// Number of glyph uploads since the display was created.
+    pub fn glyph_uploads(&self) -> usize {
+        self.uploads
+    }
+}
+
+impl Default for MemoryDisplay {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl TextDisplay for MemoryDisplay {
+    type Error = Infallible;
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        self.cells = [[' '; COLUMNS]; ROWS];
+        self.cursor = (0, 0);
+        Ok(())
+    }
+
+    fn set_cursor_position(&mut self, column: u8, row: u8) -> Result<(), Self::Error> {
+        // Like the HD44780, any row other than 0 addresses the second line.
+        self.cursor = (column, if row == 0 { 0 } else { 1 });
+        Ok(())
+    }
+
+    fn set_blink(&mut self, blink: bool) -> Result<(), Self::Error> {
+        self.blink = blink;
+        Ok(())
+    }
+
+    fn write_str(&mut self, text: &str) -> Result<(), Self::Error> {
+        for c in text.chars() {
+            let (column, row) = self.cursor;
+            // Characters past the last column are written off-screen.
+            if (column as usize) < COLUMNS {
+                self.cells[row as usize][column as usize] = c;
+            }
+            self.cursor.0 = column.saturating_add(1);
+        }
+        Ok(())
+    }
+
+    fn set_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), Self::Error> {
+        self.rgb = (r, g, b);
+        Ok(())
+    }
+
+    fn upload_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<(), Self::Error> {
+        // Only the 3 lowest bits select the slot, like on the HD44780.
+        self.glyphs[slot as usize % CGRAM_SLOTS] = *bitmap;
+        self.uploads += 1;
+        // The address counter now points to the character generator RAM.
+        self.cursor = (u8::MAX, 0);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{MemoryDisplay, TextDisplay};
+
+    #[test]
+    fn write_at_cursor_position() {
+        let mut display = MemoryDisplay::new();
+
+        display.set_cursor_position(0, 0).unwrap();
+        display.write_str("Mercredi").unwrap();
+        display.set_cursor_position(4, 1).unwrap();
+        display.write_str("18:00").unwrap();
+
+        assert_eq!(display.row(0).as_str(), "Mercredi        ");
+        assert_eq!(display.row(1).as_str(), "    18:00       ");
+        assert_eq!(display.cursor_position(), (9, 1));
+    }
+
+    #[test]
+    fn text_past_last_column_is_hidden() {
+        let mut display = MemoryDisplay::new();
+
+        display.write_str("Sortir poubelle verte").unwrap();
+
+        assert_eq!(display.row(0).as_str(), "Sortir poubelle ");
+        assert_eq!(display.row(1).as_str(), "                ");
+    }
+
+    #[test]
+    fn clear_resets_content_and_cursor() {
+        let mut display = MemoryDisplay::new();
+        display.set_cursor_position(3, 1).unwrap();
+        display.write_str("Merci <3").unwrap();
+        display.set_rgb(0, 255, 0).unwrap();
+
+        display.clear().unwrap();
+
+        assert_eq!(display.row(1).as_str(), "                ");
+        assert_eq!(display.cursor_position(), (0, 0));
+        // The backlight is not affected by a clear
+        assert_eq!(display.rgb(), (0, 255, 0));
+    }
+}
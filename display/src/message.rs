@@ -0,0 +1,308 @@
+//! Messages longer than a row of the display.
+//!
+//! Words are wrapped over the two rows. A row that is still wider than the
+//! display scrolls horizontally, looping like a marquee.
+
+use arrayvec::ArrayString;
+use embedded_hal::blocking::delay::DelayMs;
+
+use crate::backlight::{Animation, Backlight};
+use crate::{TextDisplay, COLUMNS, ROWS};
+
+/// Time between two scroll steps.
+pub const SCROLL_STEP_MS: u32 = 350;
+/// Spaces shown between the end of a scrolling row and its beginning.
+pub const MARQUEE_GAP: usize = 3;
+
+/// A message split over the rows of the display.
+#[derive(Debug, PartialEq)]
+pub struct MessageLayout<'a> {
+    rows: [&'a str; ROWS],
+}
+
+impl<'a> MessageLayout<'a> {
+    pub fn new(text: &'a str) -> Self {
+        let text = text.trim();
+        if text.chars().count() <= COLUMNS {
+            return Self { rows: [text, ""] };
+        }
+        // Break the first row after the last word that fits in it.
+        let mut break_at = None;
+        for (column, (index, c)) in text.char_indices().enumerate() {
+            if column > COLUMNS {
+                break;
+            }
+            if c == ' ' {
+                break_at = Some(index);
+            }
+        }
+        match break_at {
+            Some(index) => Self {
+                rows: [text[..index].trim_end(), text[index..].trim_start()],
+            },
+            // The first word is too long, the whole text scrolls on the first row.
+            None => Self { rows: [text, ""] },
+        }
+    }
+
+    pub fn row(&self, row: usize) -> &'a str {
+        self.rows[row]
+    }
+
+    pub fn needs_scrolling(&self) -> bool {
+        self.rows.iter().any(|row| row.chars().count() > COLUMNS)
+    }
+
+    /// Number of steps after which every scrolling row is back at its start.
+    pub fn cycle_steps(&self) -> usize {
+        self.rows
+            .iter()
+            .map(|row| row.chars().count())
+            .filter(|&len| len > COLUMNS)
+            .fold(1, |steps, len| lcm(steps, len + MARQUEE_GAP))
+    }
+
+    /// Visible part of a row once scrolled by `step` characters, padded with spaces.
+    pub fn frame(&self, row: usize, step: usize) -> ArrayString<{ COLUMNS * 4 }> {
+        let text = self.rows[row];
+        let len = text.chars().count();
+        let mut frame = ArrayString::new();
+        if len <= COLUMNS {
+            frame.push_str(text);
+        } else {
+            let looped = text.chars().chain(core::iter::repeat_n(' ', MARQUEE_GAP));
+            for c in looped
+                .cycle()
+                .skip(step % (len + MARQUEE_GAP))
+                .take(COLUMNS)
+            {
+                frame.push(c);
+            }
+        }
+        for _ in frame.chars().count()..COLUMNS {
+            frame.push(' ');
+        }
+        frame
+    }
+
+    /// Writes both rows. The rows are padded, so no clear is needed between steps.
+    pub fn render<D: TextDisplay>(&self, display: &mut D, step: usize) -> Result<(), D::Error> {
+        for row in 0..ROWS {
+            display.set_cursor_position(0, row as u8)?;
+            display.write_str(self.frame(row, step).as_str())?;
+        }
+        Ok(())
+    }
+}
+
+/// Shows the message for `duration_ms`, scrolling it if it does not fit.
+pub fn show_message<D, DL>(
+    display: &mut D,
+    delay: &mut DL,
+    text: &str,
+    duration_ms: u32,
+) -> Result<(), D::Error>
+where
+    D: TextDisplay,
+    DL: DelayMs<u32>,
+{
+    let layout = MessageLayout::new(text);
+    layout.render(display, 0)?;
+    if !layout.needs_scrolling() {
+        delay.delay_ms(duration_ms);
+        return Ok(());
+    }
+    let mut elapsed_ms = 0;
+    let mut step = 0;
+    while elapsed_ms < duration_ms {
+        let wait_ms = SCROLL_STEP_MS.min(duration_ms - elapsed_ms);
+        delay.delay_ms(wait_ms);
+        elapsed_ms += wait_ms;
+        step += 1;
+        if elapsed_ms < duration_ms {
+            layout.render(display, step)?;
+        }
+    }
+    Ok(())
+}
+
+/// Clears the display and shows the beginning of the message of a ringing
+/// alarm, starting the backlight animation. The returned `Backlight` is
+/// updated while the melody plays.
+pub fn show_ringing_message<D: TextDisplay>(
+    display: &mut D,
+    text: &str,
+    animation: Animation,
+    now_ms: u64,
+) -> Result<Backlight, D::Error> {
+    display.clear()?;
+    let mut backlight = Backlight::new(animation, now_ms);
+    backlight.update(display, now_ms)?;
+    MessageLayout::new(text).render(display, 0)?;
+    Ok(backlight)
+}
+
+fn gcd(a: usize, b: usize) -> usize {
+    if b == 0 {
+        a
+    } else {
+        gcd(b, a % b)
+    }
+}
+
+fn lcm(a: usize, b: usize) -> usize {
+    a / gcd(a, b) * b
+}
+
+#[cfg(test)]
+mod tests {
+    use embedded_hal::blocking::delay::DelayMs;
+
+    use crate::backlight::Animation;
+    use crate::message::{show_message, show_ringing_message, MessageLayout};
+    use crate::{MemoryDisplay, TextDisplay};
+
+    struct CountingDelay {
+        total_ms: u32,
+        waits: usize,
+    }
+
+    impl DelayMs<u32> for CountingDelay {
+        fn delay_ms(&mut self, ms: u32) {
+            self.total_ms += ms;
+            self.waits += 1;
+        }
+    }
+
+    #[test]
+    fn short_message_on_first_row() {
+        let layout = MessageLayout::new("Merci <3");
+
+        assert_eq!(layout.row(0), "Merci <3");
+        assert_eq!(layout.row(1), "");
+        assert!(!layout.needs_scrolling());
+    }
+
+    #[test]
+    fn long_message_wraps_on_word_boundary() {
+        let layout = MessageLayout::new("Sortir poubelle verte + verre");
+
+        assert_eq!(layout.row(0), "Sortir poubelle");
+        assert_eq!(layout.row(1), "verte + verre");
+        assert!(!layout.needs_scrolling());
+    }
+
+    #[test]
+    fn word_ending_on_last_column_stays_on_first_row() {
+        let layout = MessageLayout::new("Poubelle verte ! et jaune");
+
+        assert_eq!(layout.row(0), "Poubelle verte !");
+        assert_eq!(layout.row(1), "et jaune");
+    }
+
+    #[test]
+    fn accented_characters_count_as_one_column() {
+        let layout = MessageLayout::new("Sortir poubelle écolo");
+
+        assert_eq!(layout.row(0), "Sortir poubelle");
+        assert_eq!(layout.row(1), "écolo");
+        assert_eq!(layout.frame(1, 0).as_str(), "écolo           ");
+    }
+
+    #[test]
+    fn second_row_scrolls_when_too_long() {
+        let layout = MessageLayout::new("Sortir poubelle verte, jaune et le verre");
+
+        assert_eq!(layout.row(1), "verte, jaune et le verre");
+        assert!(layout.needs_scrolling());
+        assert_eq!(layout.cycle_steps(), 27);
+        assert_eq!(layout.frame(0, 5).as_str(), "Sortir poubelle ");
+        assert_eq!(layout.frame(1, 0).as_str(), "verte, jaune et ");
+        assert_eq!(layout.frame(1, 7).as_str(), "jaune et le verr");
+        assert_eq!(layout.frame(1, 20).as_str(), "erre   verte, ja");
+        assert_eq!(layout.frame(1, 27).as_str(), "verte, jaune et ");
+    }
+
+    #[test]
+    fn single_long_word_scrolls_on_first_row() {
+        let layout = MessageLayout::new("Anticonstitutionnellement");
+
+        assert_eq!(layout.row(1), "");
+        assert_eq!(layout.frame(0, 3).as_str(), "iconstitutionnel");
+    }
+
+    #[test]
+    fn render_writes_both_padded_rows() {
+        let mut display = MemoryDisplay::new();
+        display.write_str("old content here").unwrap();
+
+        MessageLayout::new("Poubelle jaune !")
+            .render(&mut display, 0)
+            .unwrap();
+
+        assert_eq!(display.row(0).as_str(), "Poubelle jaune !");
+        assert_eq!(display.row(1).as_str(), "                ");
+    }
+
+    #[test]
+    fn show_message_scrolls_for_the_whole_duration() {
+        let mut display = MemoryDisplay::new();
+        let mut delay = CountingDelay {
+            total_ms: 0,
+            waits: 0,
+        };
+
+        show_message(
+            &mut display,
+            &mut delay,
+            "Sortir poubelle verte, jaune et le verre",
+            1000,
+        )
+        .unwrap();
+
+        assert_eq!(delay.total_ms, 1000);
+        // 350 + 350 + 300 ms
+        assert_eq!(delay.waits, 3);
+        assert_eq!(display.row(1).as_str(), "rte, jaune et le");
+    }
+
+    #[test]
+    fn show_message_waits_once_when_it_fits() {
+        let mut display = MemoryDisplay::new();
+        let mut delay = CountingDelay {
+            total_ms: 0,
+            waits: 0,
+        };
+
+        show_message(&mut display, &mut delay, "Merci <3", 5000).unwrap();
+
+        assert_eq!(delay.total_ms, 5000);
+        assert_eq!(delay.waits, 1);
+        assert_eq!(display.row(0).as_str(), "Merci <3        ");
+    }
+
+    #[test]
+    fn ringing_message_starts_the_animation() {
+        let mut display = MemoryDisplay::new();
+        display.write_str("old content here").unwrap();
+        let pulse = Animation::Pulse {
+            color: (0, 255, 0),
+            period_ms: 1000,
+            decay_ms: 600,
+        };
+
+        let mut backlight = show_ringing_message(
+            &mut display,
+            "Sortir poubelle verte, jaune et le verre",
+            pulse,
+            5000,
+        )
+        .unwrap();
+
+        assert_eq!(display.row(0).as_str(), "Sortir poubelle ");
+        assert_eq!(display.row(1).as_str(), "verte, jaune et ");
+        assert_eq!(display.rgb(), (0, 255, 0));
+        backlight.update(&mut display, 5600).unwrap();
+        assert_ne!(display.rgb(), (0, 255, 0));
+    }
+}
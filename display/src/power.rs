@@ -0,0 +1,180 @@
+//! Presence policy of the display.
+//!
+//! Nobody looks at the clock most of the day. `PowerPolicy` dims the
+//! backlight, then turns it off and blanks the screen, once nobody has been
+//! seen for a while; motion or a button wakes it up. A pending alarm keeps
+//! the display awake at full brightness, and a night limit caps the
+//! brightness at the hours given.
+
+/// Full brightness of the backlight, `Backlight::set_level` scale.
+pub const FULL_LEVEL: u8 = 255;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PowerState {
+    Awake,
+    // Backlight at `dim_level`
+    Dimmed,
+    // Backlight off and screen cleared
+    Asleep,
+}
+
+/// Brightness cap from `from_hour` to `to_hour`, over midnight if needed.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct NightLimit {
+    pub from_hour: u8,
+    pub to_hour: u8,
+    pub max_level: u8,
+}
+
+impl NightLimit {
+    pub fn contains(&self, hour: u8) -> bool {
+        if self.from_hour <= self.to_hour {
+            hour >= self.from_hour && hour < self.to_hour
+        } else {
+            hour >= self.from_hour || hour < self.to_hour
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PowerConfig {
+    pub dim_after_ms: u64,
+    pub sleep_after_ms: u64,
+    pub dim_level: u8,
+    pub night: Option<NightLimit>,
+}
+
+impl Default for PowerConfig {
+    fn default() -> Self {
+        Self {
+            dim_after_ms: 2 * 60 * 1000,
+            sleep_after_ms: 10 * 60 * 1000,
+            dim_level: 64,
+            night: Some(NightLimit {
+                from_hour: 22,
+                to_hour: 7,
+                max_level: 32,
+            }),
+        }
+    }
+}
+
+pub struct PowerPolicy {
+    config: PowerConfig,
+    last_presence_ms: u64,
+    alarm_pending: bool,
+}
+
+impl PowerPolicy {
+    /// Starts awake, as if somebody had just been seen.
+    pub fn new(config: PowerConfig, now_ms: u64) -> Self {
+        Self {
+            config,
+            last_presence_ms: now_ms,
+            alarm_pending: false,
+        }
+    }
+
+    pub fn config(&self) -> &PowerConfig {
+        &self.config
+    }
+
+    pub fn set_config(&mut self, config: PowerConfig) {
+        self.config = config;
+    }
+
+    /// Somebody moved or pressed a button.
+    pub fn presence(&mut self, now_ms: u64) {
+        self.last_presence_ms = now_ms;
+    }
+
+    /// While an alarm is due, the display stays awake at full brightness.
+    pub fn set_alarm_pending(&mut self, alarm_pending: bool) {
+        self.alarm_pending = alarm_pending;
+    }
+
+    pub fn state(&self, now_ms: u64) -> PowerState {
+        let idle_ms = now_ms.saturating_sub(self.last_presence_ms);
+        if self.alarm_pending || idle_ms < self.config.dim_after_ms {
+            PowerState::Awake
+        } else if idle_ms < self.config.sleep_after_ms {
+            PowerState::Dimmed
+        } else {
+            PowerState::Asleep
+        }
+    }
+
+    /// Brightness of the backlight at `now_ms`, during the hour `hour`.
+    pub fn level(&self, now_ms: u64, hour: u8) -> u8 {
+        let level = match self.state(now_ms) {
+            PowerState::Awake => FULL_LEVEL,
+            PowerState::Dimmed => self.config.dim_level,
+            PowerState::Asleep => 0,
+        };
+        match self.config.night {
+            Some(night) if !self.alarm_pending && night.contains(hour) => {
+                level.min(night.max_level)
+            }
+            _ => level,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::power::{NightLimit, PowerConfig, PowerPolicy, PowerState, FULL_LEVEL};
+
+    const CONFIG: PowerConfig = PowerConfig {
+        dim_after_ms: 1000,
+        sleep_after_ms: 5000,
+        dim_level: 64,
+        night: Some(NightLimit {
+            from_hour: 22,
+            to_hour: 7,
+            max_level: 16,
+        }),
+    };
+
+    #[test]
+    fn dims_then_sleeps_without_presence() {
+        let policy = PowerPolicy::new(CONFIG, 0);
+
+        assert_eq!(policy.state(999), PowerState::Awake);
+        assert_eq!(policy.state(1000), PowerState::Dimmed);
+        assert_eq!(policy.level(1000, 12), 64);
+        assert_eq!(policy.state(5000), PowerState::Asleep);
+        assert_eq!(policy.level(5000, 12), 0);
+    }
+
+    #[test]
+    fn presence_wakes_up() {
+        let mut policy = PowerPolicy::new(CONFIG, 0);
+
+        policy.presence(6000);
+
+        assert_eq!(policy.state(6500), PowerState::Awake);
+        assert_eq!(policy.level(6500, 12), FULL_LEVEL);
+    }
+
+    #[test]
+    fn night_limit_wraps_over_midnight() {
+        let policy = PowerPolicy::new(CONFIG, 0);
+
+        assert_eq!(policy.level(0, 23), 16);
+        assert_eq!(policy.level(0, 3), 16);
+        assert_eq!(policy.level(0, 7), FULL_LEVEL);
+        assert_eq!(policy.level(0, 21), FULL_LEVEL);
+    }
+
+    #[test]
+    fn pending_alarm_overrides_the_policy() {
+        let mut policy = PowerPolicy::new(CONFIG, 0);
+
+        policy.set_alarm_pending(true);
+
+        assert_eq!(policy.state(60_000), PowerState::Awake);
+        assert_eq!(policy.level(60_000, 23), FULL_LEVEL);
+        policy.set_alarm_pending(false);
+        assert_eq!(policy.state(60_000), PowerState::Asleep);
+    }
+}
@@ -0,0 +1,84 @@
+//! Screens made of a text on each row.
+
+use clock::DateTime;
+use locale::WEEKDAY_COLUMNS;
+
+use crate::format::{day_of_week_string, short_date_string, time_string};
+use crate::TextDisplay;
+
+pub trait WriteRows: TextDisplay {
+    // Writes each text at the start of its row
+    fn write_rows(&mut self, first: &str, second: &str) -> Result<(), Self::Error>;
+}
+
+impl<D: TextDisplay> WriteRows for D {
+    fn write_rows(&mut self, first: &str, second: &str) -> Result<(), Self::Error> {
+        self.set_cursor_position(0, 0)?;
+        self.write_str(first)?;
+        self.set_cursor_position(0, 1)?;
+        self.write_str(second)
+    }
+}
+
+pub trait WriteCurrentDayAndTime: TextDisplay {
+    fn write_current_day_and_time(&mut self, time: &DateTime) -> Result<(), Self::Error>;
+    // Same with the day and month after the weekday
+    fn write_current_date_and_time(&mut self, time: &DateTime) -> Result<(), Self::Error>;
+}
+
+impl<D: TextDisplay> WriteCurrentDayAndTime for D {
+    fn write_current_day_and_time(&mut self, time: &DateTime) -> Result<(), Self::Error> {
+        self.set_cursor_position(0, 0)?;
+        self.write_str(day_of_week_string(time.day_of_week()).as_str())?;
+        self.set_cursor_position(0, 1)?;
+        self.write_str(time_string(time).as_str())
+    }
+
+    fn write_current_date_and_time(&mut self, time: &DateTime) -> Result<(), Self::Error> {
+        self.write_current_day_and_time(time)?;
+        self.set_cursor_position((WEEKDAY_COLUMNS + 1) as u8, 0)?;
+        self.write_str(short_date_string(time).as_str())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use clock::DateTime;
+    use locale::LANGUAGE;
+
+    use crate::rows::{WriteCurrentDayAndTime, WriteRows};
+    use crate::{MemoryDisplay, TextDisplay};
+
+    #[test]
+    fn rows_start_at_the_first_column() {
+        let mut display = MemoryDisplay::new();
+        display.write_str("################").unwrap();
+
+        display.write_rows("Lundi", "18:00").unwrap();
+
+        assert_eq!(display.row(0).as_str(), "Lundi###########");
+        assert_eq!(display.row(1).as_str(), "18:00           ");
+    }
+
+    #[test]
+    fn date_follows_the_weekday() {
+        let mut display = MemoryDisplay::new();
+        // A Wednesday
+        let time = DateTime {
+            year: 2024,
+            month: 12,
+            day: 25,
+            hour: 18,
+            minute: 30,
+            second: 5,
+        };
+
+        display.write_current_date_and_time(&time).unwrap();
+
+        assert_eq!(
+            display.row(0).as_str(),
+            format!("{: <11}25/12", LANGUAGE.weekday(3))
+        );
+        assert_eq!(display.row(1).as_str(), "18:30:05        ");
+    }
+}
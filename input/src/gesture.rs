@@ -0,0 +1,337 @@
+//! Gesture recognition of one button.
+//!
+//! A change of the raw level is only accepted once it has been stable for
+//! `debounce_ms`. A release before `long_press_ms` is a click: a second
+//! click within `double_press_ms` makes a `DoublePress`, otherwise the first
+//! one is reported as a `ShortPress` once the window is over. Holding the
+//! button gives a `LongPress`, then a `Repeat` every `repeat_ms` until it is
+//! released.
+
+use arrayvec::ArrayVec;
+
+use crate::queue::Edge;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ButtonEvent {
+    /// The button went down, sent before any other gesture.
+    Pressed,
+    /// The button went up.
+    Released,
+    ShortPress,
+    LongPress,
+    DoublePress,
+    /// Sent periodically while the button is held after a long press.
+    Repeat,
+}
+
+/// Events recognised by one update.
+pub type Events = ArrayVec<ButtonEvent, 6>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GestureConfig {
+    pub debounce_ms: u32,
+    pub long_press_ms: u32,
+    /// 0 disables double presses, short presses are then sent on release.
+    pub double_press_ms: u32,
+    pub repeat_ms: u32,
+}
+
+impl Default for GestureConfig {
+    fn default() -> Self {
+        Self {
+            debounce_ms: 20,
+            long_press_ms: 800,
+            double_press_ms: 300,
+            repeat_ms: 150,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub struct Gestures {
+    config: GestureConfig,
+    // Last raw level and since when it has not changed
+    raw: bool,
+    raw_since_ms: u64,
+    pressed: bool,
+    pressed_since_ms: u64,
+    // Time of the release of a click waiting for a second one
+    click_ms: Option<u64>,
+    second_click: bool,
+    // Time of the next repeat, set once the long press is sent
+    next_repeat_ms: Option<u64>,
+}
+
+impl Gestures {
+    pub fn new(config: GestureConfig) -> Self {
+        Self {
+            config,
+            raw: false,
+            raw_since_ms: 0,
+            pressed: false,
+            pressed_since_ms: 0,
+            click_ms: None,
+            second_click: false,
+            next_repeat_ms: None,
+        }
+    }
+
+    pub fn config(&self) -> &GestureConfig {
+        &self.config
+    }
+
+    /// Debounced state of the button.
+    pub fn is_pressed(&self) -> bool {
+        self.pressed
+    }
+
+    /// Feeds the level of the button at `now_ms`. It should be sampled more
+    /// often than the debounce and repeat durations.
+    pub fn update(&mut self, is_pressed: bool, now_ms: u64) -> Events {
+        let mut events = Events::new();
+        if is_pressed != self.raw {
+            self.raw = is_pressed;
+            self.raw_since_ms = now_ms;
+        }
+        let stable = now_ms.saturating_sub(self.raw_since_ms) >= self.config.debounce_ms as u64;
+        if stable && self.raw != self.pressed {
+            self.pressed = self.raw;
+            if self.pressed {
+                self.press(&mut events);
+            } else {
+                self.release(&mut events, now_ms);
+            }
+        }
+        if self.pressed {
+            self.hold(&mut events, now_ms);
+        } else if let Some(click_ms) = self.click_ms {
+            if now_ms.saturating_sub(click_ms) > self.config.double_press_ms as u64 {
+                self.click_ms = None;
+                events.push(ButtonEvent::ShortPress);
+            }
+        }
+        events
+    }
+
+    /// Lets the time pass without any change of level.
+    pub fn tick(&mut self, now_ms: u64) -> Events {
+        self.update(self.raw, now_ms)
+    }
+
+    /// Feeds a timestamped edge, the level is assumed unchanged since the
+    /// previous one.
+    pub fn edge(&mut self, edge: Edge) -> Events {
+        let mut events = self.tick(edge.at_ms);
+        events.extend(self.update(edge.active, edge.at_ms));
+        events
+    }
+
+    fn press(&mut self, events: &mut Events) {
+        // The edge happened when the level changed, not when it settled.
+        self.pressed_since_ms = self.raw_since_ms;
+        self.next_repeat_ms = None;
+        self.second_click = self.click_ms.take().is_some();
+        events.push(ButtonEvent::Pressed);
+    }
+
+    fn release(&mut self, events: &mut Events, now_ms: u64) {
+        events.push(ButtonEvent::Released);
+        if self.next_repeat_ms.is_some() {
+            return;
+        }
+        if self.second_click {
+            self.second_click = false;
+            events.push(ButtonEvent::DoublePress);
+        } else if self.config.double_press_ms == 0 {
+            events.push(ButtonEvent::ShortPress);
+        } else {
+            self.click_ms = Some(now_ms);
+        }
+    }
+
+    fn hold(&mut self, events: &mut Events, now_ms: u64) {
+        match self.next_repeat_ms {
+            None => {
+                let held_ms = now_ms.saturating_sub(self.pressed_since_ms);
+                if held_ms >= self.config.long_press_ms as u64 {
+                    // A click followed by a long press
+                    if self.second_click {
+                        self.second_click = false;
+                        events.push(ButtonEvent::ShortPress);
+                    }
+                    events.push(ButtonEvent::LongPress);
+                    self.next_repeat_ms = Some(now_ms + self.config.repeat_ms as u64);
+                }
+            }
+            Some(next_repeat_ms) if now_ms >= next_repeat_ms => {
+                events.push(ButtonEvent::Repeat);
+                self.next_repeat_ms = Some(next_repeat_ms + self.config.repeat_ms as u64);
+            }
+            Some(_) => {}
+        }
+    }
+}
+
+impl Default for Gestures {
+    fn default() -> Self {
+        Self::new(GestureConfig::default())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::gesture::{ButtonEvent, GestureConfig, Gestures};
+    use crate::queue::Edge;
+
+    // Samples the button every 10 ms from `from_ms` to `to_ms` excluded.
+    fn sample(
+        gestures: &mut Gestures,
+        pressed: bool,
+        from_ms: u64,
+        to_ms: u64,
+    ) -> Vec<ButtonEvent> {
+        (from_ms..to_ms)
+            .step_by(10)
+            .flat_map(|now_ms| gestures.update(pressed, now_ms))
+            .collect()
+    }
+
+    #[test]
+    fn bounces_are_ignored() {
+        let mut gestures = Gestures::default();
+
+        let mut events = Vec::new();
+        for (now_ms, pressed) in [(0, true), (5, false), (10, true), (15, false), (20, true)] {
+            events.extend(gestures.update(pressed, now_ms));
+        }
+        assert_eq!(events, vec![]);
+        assert_eq!(
+            sample(&mut gestures, true, 40, 50),
+            vec![ButtonEvent::Pressed]
+        );
+    }
+
+    #[test]
+    fn short_press_waits_for_the_double_press_window() {
+        let mut gestures = Gestures::default();
+
+        assert_eq!(
+            sample(&mut gestures, true, 0, 100),
+            vec![ButtonEvent::Pressed]
+        );
+        assert_eq!(
+            sample(&mut gestures, false, 100, 400),
+            vec![ButtonEvent::Released]
+        );
+        assert_eq!(
+            sample(&mut gestures, false, 400, 500),
+            vec![ButtonEvent::ShortPress]
+        );
+    }
+
+    #[test]
+    fn short_press_without_double_press() {
+        let mut gestures = Gestures::new(GestureConfig {
+            double_press_ms: 0,
+            ..GestureConfig::default()
+        });
+
+        sample(&mut gestures, true, 0, 100);
+
+        assert_eq!(
+            sample(&mut gestures, false, 100, 200),
+            vec![ButtonEvent::Released, ButtonEvent::ShortPress]
+        );
+    }
+
+    #[test]
+    fn double_press() {
+        let mut gestures = Gestures::default();
+
+        sample(&mut gestures, true, 0, 100);
+        sample(&mut gestures, false, 100, 200);
+        assert_eq!(
+            sample(&mut gestures, true, 200, 300),
+            vec![ButtonEvent::Pressed]
+        );
+        assert_eq!(
+            sample(&mut gestures, false, 300, 1000),
+            vec![ButtonEvent::Released, ButtonEvent::DoublePress]
+        );
+    }
+
+    #[test]
+    fn long_press_then_repeats() {
+        let mut gestures = Gestures::default();
+
+        let events = sample(&mut gestures, true, 0, 1300);
+
+        assert_eq!(
+            events,
+            vec![
+                ButtonEvent::Pressed,
+                ButtonEvent::LongPress,
+                ButtonEvent::Repeat,
+                ButtonEvent::Repeat,
+                ButtonEvent::Repeat,
+            ]
+        );
+        // No short press after a long one
+        assert_eq!(
+            sample(&mut gestures, false, 1300, 2000),
+            vec![ButtonEvent::Released]
+        );
+    }
+
+    #[test]
+    fn click_then_long_press() {
+        let mut gestures = Gestures::default();
+
+        sample(&mut gestures, true, 0, 100);
+        sample(&mut gestures, false, 100, 200);
+
+        assert_eq!(
+            sample(&mut gestures, true, 200, 1100),
+            vec![
+                ButtonEvent::Pressed,
+                ButtonEvent::ShortPress,
+                ButtonEvent::LongPress,
+            ]
+        );
+    }
+
+    #[test]
+    fn sparse_samples_still_see_a_long_press() {
+        // The alarm melody only checks the button between notes.
+        let mut gestures = Gestures::default();
+
+        let mut events = Vec::new();
+        for now_ms in [0, 400, 900] {
+            events.extend(gestures.update(true, now_ms));
+        }
+
+        assert_eq!(events, vec![ButtonEvent::Pressed, ButtonEvent::LongPress]);
+    }
+
+    #[test]
+    fn edges_read_late_keep_their_timing() {
+        // A two seconds press queued while the application was busy
+        let mut gestures = Gestures::default();
+
+        let mut events = Vec::new();
+        for (active, at_ms) in [(true, 0), (false, 5), (true, 10), (false, 2000)] {
+            events.extend(gestures.edge(Edge { active, at_ms }));
+        }
+        events.extend(gestures.tick(5000));
+
+        assert_eq!(
+            events,
+            vec![
+                ButtonEvent::Pressed,
+                // Missed repeats are not replayed
+                ButtonEvent::LongPress,
+                ButtonEvent::Released,
+            ]
+        );
+    }
+}
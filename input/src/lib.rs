@@ -0,0 +1,14 @@
+#![cfg_attr(not(test), no_std)]
+
+//! Button inputs, independent of the hardware.
+//!
+//! Buttons are sampled with a millisecond timestamp, `Gestures` debounces
+//! them and recognises presses, long presses, double presses and the
+//! auto-repeat of a held button. Edges caught by interrupts wait in an
+//! `EventQueue` until the application reads them.
+
+pub mod gesture;
+pub mod queue;
+
+pub use gesture::{ButtonEvent, Events, GestureConfig, Gestures};
+pub use queue::{Edge, EventQueue};
@@ -0,0 +1,121 @@
+//! Fixed capacity queue of input events.
+//!
+//! Interrupt handlers push the edges of the inputs with their timestamp, the
+//! application pops them when it has time. When the application falls
+//! behind, the oldest events are dropped so that the latest state of the
+//! input is never lost.
+
+/// Change of level of an input.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Edge {
+    /// Pressed button or detected motion.
+    pub active: bool,
+    pub at_ms: u64,
+}
+
+#[derive(Debug)]
+pub struct EventQueue<T: Copy, const N: usize> {
+    events: [Option<T>; N],
+    // Index of the oldest event
+    head: usize,
+    len: usize,
+    dropped: usize,
+}
+
+impl<T: Copy, const N: usize> EventQueue<T, N> {
+    pub const fn new() -> Self {
+        Self {
+            events: [None; N],
+            head: 0,
+            len: 0,
+            dropped: 0,
+        }
+    }
+
+    /// Adds an event, dropping the oldest one when the queue is full.
+    pub fn push(&mut self, event: T) {
+        if N == 0 {
+            self.dropped += 1;
+            return;
+        }
+        if self.len == N {
+            self.head = (self.head + 1) % N;
+            self.len -= 1;
+            self.dropped += 1;
+        }
+        self.events[(self.head + self.len) % N] = Some(event);
+        self.len += 1;
+    }
+
+    /// Removes the oldest event.
+    pub fn pop(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let event = self.events[self.head].take();
+        self.head = (self.head + 1) % N;
+        self.len -= 1;
+        event
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop().is_some() {}
+    }
+
+    /// Number of events dropped because the queue was full.
+    pub fn dropped(&self) -> usize {
+        self.dropped
+    }
+}
+
+impl<T: Copy, const N: usize> Default for EventQueue<T, N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::queue::EventQueue;
+
+    #[test]
+    fn events_come_out_in_order() {
+        let mut queue = EventQueue::<u8, 4>::new();
+
+        for event in 0..3 {
+            queue.push(event);
+        }
+        assert_eq!(queue.pop(), Some(0));
+        queue.push(3);
+        queue.push(4);
+
+        assert_eq!(queue.len(), 4);
+        assert_eq!(
+            core::iter::from_fn(|| queue.pop()).collect::<Vec<_>>(),
+            vec![1, 2, 3, 4]
+        );
+        assert!(queue.is_empty());
+    }
+
+    #[test]
+    fn full_queue_drops_the_oldest_events() {
+        let mut queue = EventQueue::<u8, 2>::new();
+
+        for event in 0..5 {
+            queue.push(event);
+        }
+
+        assert_eq!(queue.dropped(), 3);
+        assert_eq!(queue.pop(), Some(3));
+        assert_eq!(queue.pop(), Some(4));
+        assert_eq!(queue.pop(), None);
+    }
+}
@@ -0,0 +1,425 @@
+#![cfg_attr(not(test), no_std)]
+
+//! Texts shown to the user, in French, English and German.
+//!
+//! The language of the firmware is chosen at build time with the `english`
+//! or `german` features, French otherwise. Every text has a maximum width in
+//! columns and in bytes, matching the `ArrayString` it is rendered into; the
+//! tests check all the translations against them.
+
+/// Columns of the display.
+const ROW: usize = 16;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Language {
+    French,
+    English,
+    German,
+}
+
+#[cfg(feature = "german")]
+pub const LANGUAGE: Language = Language::German;
+#[cfg(all(feature = "english", not(feature = "german")))]
+pub const LANGUAGE: Language = Language::English;
+#[cfg(not(any(feature = "english", feature = "german")))]
+pub const LANGUAGE: Language = Language::French;
+
+pub const LANGUAGES: [Language; 3] = [Language::French, Language::English, Language::German];
+
+/// Columns taken by the longest weekday name.
+pub const WEEKDAY_COLUMNS: usize = 10;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Text {
+    // Date and time entry prompts
+    EnterYear,
+    EnterMonth,
+    EnterDay,
+    EnterHourTens,
+    EnterHourUnits,
+    EnterMinuteTens,
+    EnterMinuteUnits,
+    EntryFinished,
+    // Alarm descriptions
+    GreenBin,
+    YellowBin,
+    Glass,
+    Compost,
+    BulkyWaste,
+    // Messages shown while the alarms ring
+    TakeOutGreenBin,
+    TakeOutYellowBin,
+    TakeOutGlass,
+    TakeOutCompost,
+    TakeOutBulkyWaste,
+    Thanks,
+    // Alarm settings
+    Alarms,
+    Enabled,
+    Weekday,
+    Hour,
+    Minute,
+    Duration,
+    Message,
+    Colour,
+    Yes,
+    No,
+    Back,
+    Green,
+    Yellow,
+    Blue,
+    Red,
+    Brown,
+    White,
+    // Idle dashboard
+    NextIn,
+    Now,
+    NoAlarm,
+    Missed,
+    Uptime,
+    DayUnit,
+    // Diagnostics page of the dashboard
+    Drift,
+    LastSync,
+}
+
+pub const TEXTS: [Text; 44] = [
+    Text::EnterYear,
+    Text::EnterMonth,
+    Text::EnterDay,
+    Text::EnterHourTens,
+    Text::EnterHourUnits,
+    Text::EnterMinuteTens,
+    Text::EnterMinuteUnits,
+    Text::EntryFinished,
+    Text::GreenBin,
+    Text::YellowBin,
+    Text::Glass,
+    Text::Compost,
+    Text::BulkyWaste,
+    Text::TakeOutGreenBin,
+    Text::TakeOutYellowBin,
+    Text::TakeOutGlass,
+    Text::TakeOutCompost,
+    Text::TakeOutBulkyWaste,
+    Text::Thanks,
+    Text::Alarms,
+    Text::Enabled,
+    Text::Weekday,
+    Text::Hour,
+    Text::Minute,
+    Text::Duration,
+    Text::Message,
+    Text::Colour,
+    Text::Yes,
+    Text::No,
+    Text::Back,
+    Text::Green,
+    Text::Yellow,
+    Text::Blue,
+    Text::Red,
+    Text::Brown,
+    Text::White,
+    Text::NextIn,
+    Text::Now,
+    Text::NoAlarm,
+    Text::Missed,
+    Text::Uptime,
+    Text::DayUnit,
+    Text::Drift,
+    Text::LastSync,
+];
+
+/// Room available for a text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Limit {
+    pub columns: usize,
+    pub bytes: usize,
+}
+
+impl Text {
+    pub fn limit(self) -> Limit {
+        match self {
+            // `ArrayString<16>` of the alarm descriptions
+            Text::GreenBin | Text::YellowBin | Text::Glass | Text::Compost | Text::BulkyWaste => {
+                Limit {
+                    columns: ROW,
+                    bytes: 16,
+                }
+            }
+            // Scrolled `display::Message`
+            Text::TakeOutGreenBin
+            | Text::TakeOutYellowBin
+            | Text::TakeOutGlass
+            | Text::TakeOutCompost
+            | Text::TakeOutBulkyWaste
+            | Text::Thanks => Limit {
+                columns: 64,
+                bytes: 64,
+            },
+            // Shown next to each other in confirmations
+            Text::Yes | Text::No => Limit {
+                columns: 6,
+                bytes: 24,
+            },
+            // Followed by a countdown such as " 6j 23h"
+            Text::NextIn | Text::Uptime => Limit {
+                columns: 8,
+                bytes: 32,
+            },
+            Text::DayUnit => Limit {
+                columns: 1,
+                bytes: 4,
+            },
+            // Followed by a drift such as " +46.3ppm"
+            Text::Drift => Limit {
+                columns: 6,
+                bytes: 24,
+            },
+            // Followed by the time since the sync, such as " 4h 05min"
+            Text::LastSync => Limit {
+                columns: 7,
+                bytes: 28,
+            },
+            // A row of up to 4 bytes characters
+            _ => Limit {
+                columns: ROW,
+                bytes: 4 * ROW,
+            },
+        }
+    }
+}
+
+impl Language {
+    pub fn text(self, text: Text) -> &'static str {
+        match self {
+            Language::French => french(text),
+            Language::English => english(text),
+            Language::German => german(text),
+        }
+    }
+
+    /// Name of the day of the week, 0 being Sunday.
+    pub fn weekday(self, day_of_week: u8) -> &'static str {
+        let names = match self {
+            Language::French => [
+                "Dimanche", "Lundi", "Mardi", "Mercredi", "Jeudi", "Vendredi", "Samedi",
+            ],
+            Language::English => [
+                "Sunday",
+                "Monday",
+                "Tuesday",
+                "Wednesday",
+                "Thursday",
+                "Friday",
+                "Saturday",
+            ],
+            Language::German => [
+                "Sonntag",
+                "Montag",
+                "Dienstag",
+                "Mittwoch",
+                "Donnerstag",
+                "Freitag",
+                "Samstag",
+            ],
+        };
+        names[day_of_week as usize % 7]
+    }
+}
+
+fn french(text: Text) -> &'static str {
+    match text {
+        Text::EnterYear => "Année ?",
+        Text::EnterMonth => "Mois ?",
+        Text::EnterDay => "Jour ?",
+        Text::EnterHourTens => "Dizaine Heure ?",
+        Text::EnterHourUnits => "Unité Heure ?",
+        Text::EnterMinuteTens => "Dizaine Minute ?",
+        Text::EnterMinuteUnits => "Unité Minute ?",
+        Text::EntryFinished => "Terminé",
+        Text::GreenBin => "Poubelle verte !",
+        Text::YellowBin => "Poubelle jaune !",
+        Text::Glass => "Verre !",
+        Text::Compost => "Compost !",
+        Text::BulkyWaste => "Encombrants !",
+        Text::TakeOutGreenBin => "🗑 Sortir la poubelle verte !",
+        Text::TakeOutYellowBin => "🗑 Sortir la poubelle jaune !",
+        Text::TakeOutGlass => "🗑 Sortir le verre !",
+        Text::TakeOutCompost => "🗑 Sortir le compost !",
+        Text::TakeOutBulkyWaste => "🗑 Sortir les encombrants !",
+        Text::Thanks => "Merci ♥",
+        Text::Alarms => "Alarmes",
+        Text::Enabled => "Activée",
+        Text::Weekday => "Jour",
+        Text::Hour => "Heure",
+        Text::Minute => "Minute",
+        Text::Duration => "Durée",
+        Text::Message => "Texte",
+        Text::Colour => "Couleur",
+        Text::Yes => "Oui",
+        Text::No => "Non",
+        Text::Back => "Retour",
+        Text::Green => "Vert",
+        Text::Yellow => "Jaune",
+        Text::Blue => "Bleu",
+        Text::Red => "Rouge",
+        Text::Brown => "Marron",
+        Text::White => "Blanc",
+        Text::NextIn => "dans",
+        Text::Now => "Maintenant !",
+        Text::NoAlarm => "Aucune alarme",
+        Text::Missed => "Manquée :",
+        Text::Uptime => "Allumée",
+        Text::DayUnit => "j",
+        Text::Drift => "Dérive",
+        Text::LastSync => "Synchro",
+    }
+}
+
+fn english(text: Text) -> &'static str {
+    match text {
+        Text::EnterYear => "Year?",
+        Text::EnterMonth => "Month?",
+        Text::EnterDay => "Day?",
+        Text::EnterHourTens => "Hour tens?",
+        Text::EnterHourUnits => "Hour units?",
+        Text::EnterMinuteTens => "Minute tens?",
+        Text::EnterMinuteUnits => "Minute units?",
+        Text::EntryFinished => "Done",
+        Text::GreenBin => "Green bin!",
+        Text::YellowBin => "Yellow bin!",
+        Text::Glass => "Glass!",
+        Text::Compost => "Compost!",
+        Text::BulkyWaste => "Bulky waste!",
+        Text::TakeOutGreenBin => "🗑 Take out the green bin!",
+        Text::TakeOutYellowBin => "🗑 Take out the yellow bin!",
+        Text::TakeOutGlass => "🗑 Take out the glass!",
+        Text::TakeOutCompost => "🗑 Take out the compost!",
+        Text::TakeOutBulkyWaste => "🗑 Take out the bulky waste!",
+        Text::Thanks => "Thank you ♥",
+        Text::Alarms => "Alarms",
+        Text::Enabled => "Enabled",
+        Text::Weekday => "Day",
+        Text::Hour => "Hour",
+        Text::Minute => "Minute",
+        Text::Duration => "Duration",
+        Text::Message => "Text",
+        Text::Colour => "Colour",
+        Text::Yes => "Yes",
+        Text::No => "No",
+        Text::Back => "Back",
+        Text::Green => "Green",
+        Text::Yellow => "Yellow",
+        Text::Blue => "Blue",
+        Text::Red => "Red",
+        Text::Brown => "Brown",
+        Text::White => "White",
+        Text::NextIn => "in",
+        Text::Now => "Now!",
+        Text::NoAlarm => "No alarm",
+        Text::Missed => "Missed:",
+        Text::Uptime => "Up for",
+        Text::DayUnit => "d",
+        Text::Drift => "Drift",
+        Text::LastSync => "Synced",
+    }
+}
+
+fn german(text: Text) -> &'static str {
+    match text {
+        Text::EnterYear => "Jahr?",
+        Text::EnterMonth => "Monat?",
+        Text::EnterDay => "Tag?",
+        Text::EnterHourTens => "Stunde Zehner?",
+        Text::EnterHourUnits => "Stunde Einer?",
+        Text::EnterMinuteTens => "Minute Zehner?",
+        Text::EnterMinuteUnits => "Minute Einer?",
+        Text::EntryFinished => "Fertig",
+        Text::GreenBin => "Grüne Tonne!",
+        Text::YellowBin => "Gelbe Tonne!",
+        Text::Glass => "Glas!",
+        Text::Compost => "Biotonne!",
+        Text::BulkyWaste => "Sperrmüll!",
+        Text::TakeOutGreenBin => "🗑 Grüne Tonne rausstellen!",
+        Text::TakeOutYellowBin => "🗑 Gelbe Tonne rausstellen!",
+        Text::TakeOutGlass => "🗑 Glas wegbringen!",
+        Text::TakeOutCompost => "🗑 Biotonne rausstellen!",
+        Text::TakeOutBulkyWaste => "🗑 Sperrmüll rausstellen!",
+        Text::Thanks => "Danke ♥",
+        Text::Alarms => "Wecker",
+        Text::Enabled => "Aktiv",
+        Text::Weekday => "Tag",
+        Text::Hour => "Stunde",
+        Text::Minute => "Minute",
+        Text::Duration => "Dauer",
+        Text::Message => "Text",
+        Text::Colour => "Farbe",
+        Text::Yes => "Ja",
+        Text::No => "Nein",
+        Text::Back => "Zurück",
+        Text::Green => "Grün",
+        Text::Yellow => "Gelb",
+        Text::Blue => "Blau",
+        Text::Red => "Rot",
+        Text::Brown => "Braun",
+        Text::White => "Weiß",
+        Text::NextIn => "in",
+        Text::Now => "Jetzt!",
+        Text::NoAlarm => "Kein Wecker",
+        Text::Missed => "Verpasst:",
+        Text::Uptime => "Läuft",
+        Text::DayUnit => "T",
+        Text::Drift => "Gang",
+        Text::LastSync => "Sync",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Language, Text, LANGUAGES, TEXTS, WEEKDAY_COLUMNS};
+
+    #[test]
+    fn texts_fit_their_buffers() {
+        for language in LANGUAGES {
+            for text in TEXTS {
+                let (string, limit) = (language.text(text), text.limit());
+                assert!(
+                    string.chars().count() <= limit.columns && string.len() <= limit.bytes,
+                    "{:?} {:?} is too long: {:?}",
+                    language,
+                    text,
+                    string
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn weekdays_fit_their_columns() {
+        for language in LANGUAGES {
+            for day in 0..7 {
+                let name = language.weekday(day);
+                assert!(name.is_ascii() && name.len() <= WEEKDAY_COLUMNS, "{}", name);
+            }
+        }
+    }
+
+    #[test]
+    fn confirmation_fits_a_row() {
+        // ">Non   Oui"
+        for language in LANGUAGES {
+            let width = language.text(Text::No).chars().count()
+                + language.text(Text::Yes).chars().count()
+                + 5;
+            assert!(width <= 16);
+        }
+    }
+
+    #[test]
+    fn weekdays_start_on_sunday() {
+        assert_eq!(Language::French.weekday(0), "Dimanche");
+        assert_eq!(Language::German.weekday(4), "Donnerstag");
+        assert_eq!(Language::English.weekday(6), "Saturday");
+    }
+}
@@ -0,0 +1,650 @@
+#![cfg_attr(not(test), no_std)]
+
+//! Menus for a 16x2 display driven by two buttons.
+//!
+//! `Next` moves to the next item, or increments the value being edited, and
+//! `Validate` opens the selected item. `Back` leaves the current screen
+//! without saving; menus also end with a "Retour" item so that they can be
+//! left with the two buttons only.
+//!
+//! The content of the menus is given by a `MenuModel`, the `Navigator` only
+//! keeps track of where the user is. Both are independent of the hardware.
+
+use arrayvec::{ArrayString, ArrayVec};
+use core::fmt::Write;
+use display::{TextDisplay, COLUMNS};
+
+/// Maximum number of nested menus.
+pub const MAX_DEPTH: usize = 4;
+
+pub const BACK_LABEL: &str = "Retour";
+pub const YES_LABEL: &str = "Oui";
+pub const NO_LABEL: &str = "Non";
+
+/// Text of a row, characters can take up to 4 bytes.
+pub type Label = ArrayString<{ COLUMNS * 4 }>;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Button {
+    Next,
+    Validate,
+    Back,
+}
+
+/// Values taken by an edited field, `Next` wraps from `max` to `min`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ValueRange {
+    pub min: u16,
+    pub max: u16,
+    pub step: u16,
+}
+
+impl ValueRange {
+    pub const fn new(min: u16, max: u16) -> Self {
+        Self { min, max, step: 1 }
+    }
+
+    pub const fn with_step(self, step: u16) -> Self {
+        Self { step, ..self }
+    }
+
+    pub fn next(&self, value: u16) -> u16 {
+        match value.checked_add(self.step.max(1)) {
+            Some(next) if next <= self.max => next,
+            _ => self.min,
+        }
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Item<M, F, A> {
+    Submenu {
+        label: Label,
+        menu: M,
+    },
+    Edit {
+        label: Label,
+        field: F,
+    },
+    /// Actions with `confirm` ask "Oui" or "Non" before being returned.
+    Action {
+        label: Label,
+        action: A,
+        confirm: bool,
+    },
+    Back,
+}
+
+impl<M, F, A> Item<M, F, A> {
+    pub fn label(&self) -> Label {
+        match self {
+            Item::Submenu { label, .. } | Item::Edit { label, .. } | Item::Action { label, .. } => {
+                *label
+            }
+            Item::Back => label(BACK_LABEL),
+        }
+    }
+}
+
+/// Creates a label, truncated if longer than its capacity.
+pub fn label(text: &str) -> Label {
+    let mut label = Label::new();
+    push_truncated(&mut label, text);
+    label
+}
+
+// Appends as much of `text` as `label` can hold.
+fn push_truncated(label: &mut Label, text: &str) {
+    for c in text.chars() {
+        if label.try_push(c).is_err() {
+            break;
+        }
+    }
+}
+
+/// Content of the menus and storage of the edited values.
+pub trait MenuModel {
+    type Menu: Copy + PartialEq;
+    type Field: Copy + PartialEq;
+    type Action: Copy + PartialEq;
+
+    fn title(&self, menu: Self::Menu) -> Label;
+    fn item_count(&self, menu: Self::Menu) -> usize;
+    fn item(&self, menu: Self::Menu, index: usize) -> Item<Self::Menu, Self::Field, Self::Action>;
+
+    fn value(&self, field: Self::Field) -> u16;
+    fn range(&self, field: Self::Field) -> ValueRange;
+    fn set_value(&mut self, field: Self::Field, value: u16);
+
+    /// Text shown for a value, the number itself by default.
+    fn format_value(&self, _field: Self::Field, value: u16) -> Label {
+        let mut label = Label::new();
+        write!(label, "{}", value).unwrap();
+        label
+    }
+
+    /// Labels of the "Retour" items and of the confirmations, to translate
+    /// them.
+    fn back_label(&self) -> Label {
+        label(BACK_LABEL)
+    }
+
+    fn yes_label(&self) -> Label {
+        label(YES_LABEL)
+    }
+
+    fn no_label(&self) -> Label {
+        label(NO_LABEL)
+    }
+}
+
+/// Result of a button press.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Event<A> {
+    None,
+    /// An action has been selected, and confirmed if needed.
+    Action(A),
+    /// The root menu has been left.
+    Exit,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum Mode<F, A> {
+    Browse,
+    Edit { field: F, label: Label, value: u16 },
+    Confirm { action: A, label: Label, yes: bool },
+}
+
+/// Position of the user in the menus.
+pub struct Navigator<M: MenuModel> {
+    // Menus from the root with their selected item
+    stack: ArrayVec<(M::Menu, usize), MAX_DEPTH>,
+    mode: Mode<M::Field, M::Action>,
+}
+
+impl<M: MenuModel> Navigator<M> {
+    pub fn new(root: M::Menu) -> Self {
+        let mut stack = ArrayVec::new();
+        stack.push((root, 0));
+        Self {
+            stack,
+            mode: Mode::Browse,
+        }
+    }
+
+    pub fn menu(&self) -> M::Menu {
+        self.stack[self.stack.len() - 1].0
+    }
+
+    /// Index of the selected item in the current menu.
+    pub fn selected(&self, model: &M) -> usize {
+        let (menu, index) = self.stack[self.stack.len() - 1];
+        index % model.item_count(menu).max(1)
+    }
+
+    pub fn is_editing(&self) -> bool {
+        matches!(self.mode, Mode::Edit { .. })
+    }
+
+    pub fn is_confirming(&self) -> bool {
+        matches!(self.mode, Mode::Confirm { .. })
+    }
+
+    pub fn handle(&mut self, model: &mut M, button: Button) -> Event<M::Action> {
+        match self.mode {
+            Mode::Browse => self.browse(model, button),
+            Mode::Edit {
+                field,
+                label,
+                value,
+            } => {
+                match button {
+                    Button::Next => {
+                        self.mode = Mode::Edit {
+                            field,
+                            label,
+                            value: model.range(field).next(value),
+                        }
+                    }
+                    Button::Validate => {
+                        model.set_value(field, value);
+                        self.mode = Mode::Browse;
+                    }
+                    Button::Back => self.mode = Mode::Browse,
+                }
+                Event::None
+            }
+            Mode::Confirm { action, label, yes } => match button {
+                Button::Next => {
+                    self.mode = Mode::Confirm {
+                        action,
+                        label,
+                        yes: !yes,
+                    };
+                    Event::None
+                }
+                Button::Validate if yes => {
+                    self.mode = Mode::Browse;
+                    Event::Action(action)
+                }
+                Button::Validate | Button::Back => {
+                    self.mode = Mode::Browse;
+                    Event::None
+                }
+            },
+        }
+    }
+
+    fn browse(&mut self, model: &mut M, button: Button) -> Event<M::Action> {
+        let menu = self.menu();
+        let selected = self.selected(model);
+        match button {
+            Button::Next => {
+                let last = self.stack.len() - 1;
+                self.stack[last].1 = (selected + 1) % model.item_count(menu).max(1);
+                Event::None
+            }
+            Button::Back => self.leave(),
+            Button::Validate => match model.item(menu, selected) {
+                Item::Submenu { menu, .. } => {
+                    // Deeper menus are ignored rather than losing the way back.
+                    let _ = self.stack.try_push((menu, 0));
+                    Event::None
+                }
+                Item::Edit { label, field } => {
+                    self.mode = Mode::Edit {
+                        field,
+                        label,
+                        value: model.value(field),
+                    };
+                    Event::None
+                }
+                Item::Action {
+                    label,
+                    action,
+                    confirm,
+                } => {
+                    if confirm {
+                        self.mode = Mode::Confirm {
+                            action,
+                            label,
+                            yes: false,
+                        };
+                        Event::None
+                    } else {
+                        Event::Action(action)
+                    }
+                }
+                Item::Back => self.leave(),
+            },
+        }
+    }
+
+    fn leave(&mut self) -> Event<M::Action> {
+        if self.stack.len() > 1 {
+            self.stack.pop();
+            Event::None
+        } else {
+            self.stack[0].1 = 0;
+            Event::Exit
+        }
+    }
+
+    /// Writes both rows of the current screen.
+    pub fn render<D: TextDisplay>(&self, model: &M, display: &mut D) -> Result<(), D::Error> {
+        let (first, second) = match self.mode {
+            Mode::Browse => {
+                let item = match model.item(self.menu(), self.selected(model)) {
+                    Item::Back => model.back_label(),
+                    item => item.label(),
+                };
+                let mut second = label(">");
+                push_truncated(&mut second, item.as_str());
+                (model.title(self.menu()), second)
+            }
+            Mode::Edit {
+                field,
+                label: item,
+                value,
+            } => {
+                let mut second = label("= ");
+                push_truncated(&mut second, model.format_value(field, value).as_str());
+                (item, second)
+            }
+            Mode::Confirm {
+                label: item, yes, ..
+            } => {
+                let (no_mark, yes_mark) = if yes { (" ", ">") } else { (">", " ") };
+                let mut second = Label::new();
+                // Long labels are cut rather than overflowing the row
+                for part in [
+                    no_mark,
+                    model.no_label().as_str(),
+                    "   ",
+                    yes_mark,
+                    model.yes_label().as_str(),
+                ] {
+                    push_truncated(&mut second, part);
+                }
+                (item, second)
+            }
+        };
+        display.set_cursor_position(0, 0)?;
+        display.write_str(padded(&first).as_str())?;
+        display.set_cursor_position(0, 1)?;
+        display.write_str(padded(&second).as_str())
+    }
+}
+
+// Cut to the width of the display and padded with spaces.
+fn padded(text: &str) -> Label {
+    let mut row = Label::new();
+    for c in text.chars().take(COLUMNS) {
+        row.push(c);
+    }
+    for _ in row.chars().count()..COLUMNS {
+        row.push(' ');
+    }
+    row
+}
+
+#[cfg(test)]
+mod tests {
+    use display::MemoryDisplay;
+
+    use crate::{label, Button, Event, Item, Label, MenuModel, Navigator, ValueRange};
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Menu {
+        Root,
+        Colour,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Field {
+        Volume,
+        Red,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    enum Action {
+        Reset,
+        Save,
+    }
+
+    struct Settings {
+        volume: u16,
+        red: u16,
+        english: bool,
+        // Labels filling their whole capacity
+        long_labels: bool,
+    }
+
+    impl MenuModel for Settings {
+        type Menu = Menu;
+        type Field = Field;
+        type Action = Action;
+
+        fn title(&self, menu: Menu) -> Label {
+            label(match menu {
+                Menu::Root => "Réglages",
+                Menu::Colour => "Couleur",
+            })
+        }
+
+        fn item_count(&self, menu: Menu) -> usize {
+            match menu {
+                Menu::Root => 5,
+                Menu::Colour => 2,
+            }
+        }
+
+        fn item(&self, menu: Menu, index: usize) -> Item<Menu, Field, Action> {
+            match (menu, index) {
+                (Menu::Root, 0) => Item::Edit {
+                    label: label("Volume"),
+                    field: Field::Volume,
+                },
+                (Menu::Root, 1) => Item::Submenu {
+                    label: label("Couleur"),
+                    menu: Menu::Colour,
+                },
+                (Menu::Root, 2) => Item::Action {
+                    label: label("Réinitialiser"),
+                    action: Action::Reset,
+                    confirm: true,
+                },
+                (Menu::Root, 3) => Item::Action {
+                    label: label("Sauver"),
+                    action: Action::Save,
+                    confirm: false,
+                },
+                (Menu::Colour, 0) => Item::Edit {
+                    label: label("Rouge"),
+                    field: Field::Red,
+                },
+                _ => Item::Back,
+            }
+        }
+
+        fn value(&self, field: Field) -> u16 {
+            match field {
+                Field::Volume => self.volume,
+                Field::Red => self.red,
+            }
+        }
+
+        fn range(&self, field: Field) -> ValueRange {
+            match field {
+                Field::Volume => ValueRange::new(0, 3),
+                Field::Red => ValueRange::new(0, 255).with_step(64),
+            }
+        }
+
+        fn set_value(&mut self, field: Field, value: u16) {
+            match field {
+                Field::Volume => self.volume = value,
+                Field::Red => self.red = value,
+            }
+        }
+
+        fn back_label(&self) -> Label {
+            if self.long_labels {
+                return label(&"r".repeat(100));
+            }
+            label(if self.english { "Back" } else { "Retour" })
+        }
+
+        fn yes_label(&self) -> Label {
+            if self.long_labels {
+                return label(&"o".repeat(100));
+            }
+            label(if self.english { "Yes" } else { "Oui" })
+        }
+
+        fn no_label(&self) -> Label {
+            if self.long_labels {
+                return label(&"n".repeat(100));
+            }
+            label(if self.english { "No" } else { "Non" })
+        }
+    }
+
+    fn settings() -> (Settings, Navigator<Settings>) {
+        (
+            Settings {
+                volume: 2,
+                red: 0,
+                english: false,
+                long_labels: false,
+            },
+            Navigator::new(Menu::Root),
+        )
+    }
+
+    fn press(settings: &mut Settings, navigator: &mut Navigator<Settings>, buttons: &[Button]) {
+        for &button in buttons {
+            navigator.handle(settings, button);
+        }
+    }
+
+    fn rows(settings: &Settings, navigator: &Navigator<Settings>) -> (String, String) {
+        let mut display = MemoryDisplay::new();
+        navigator.render(settings, &mut display).unwrap();
+        (
+            display.row(0).as_str().to_string(),
+            display.row(1).as_str().to_string(),
+        )
+    }
+
+    #[test]
+    fn next_cycles_through_items() {
+        let (mut settings, mut navigator) = settings();
+
+        assert_eq!(
+            rows(&settings, &navigator),
+            ("Réglages        ".into(), ">Volume         ".into())
+        );
+        press(&mut settings, &mut navigator, &[Button::Next; 4]);
+        assert_eq!(rows(&settings, &navigator).1, ">Retour         ");
+        press(&mut settings, &mut navigator, &[Button::Next]);
+        assert_eq!(navigator.selected(&settings), 0);
+    }
+
+    #[test]
+    fn edited_value_wraps_and_is_saved_on_validate() {
+        let (mut settings, mut navigator) = settings();
+
+        press(
+            &mut settings,
+            &mut navigator,
+            &[Button::Validate, Button::Next],
+        );
+        assert!(navigator.is_editing());
+        assert_eq!(
+            rows(&settings, &navigator),
+            ("Volume          ".into(), "= 3             ".into())
+        );
+        press(&mut settings, &mut navigator, &[Button::Next]);
+        // Nothing is saved while editing
+        assert_eq!(settings.volume, 2);
+        assert_eq!(rows(&settings, &navigator).1, "= 0             ");
+
+        press(&mut settings, &mut navigator, &[Button::Validate]);
+        assert_eq!(settings.volume, 0);
+        assert!(!navigator.is_editing());
+    }
+
+    #[test]
+    fn back_cancels_an_edit() {
+        let (mut settings, mut navigator) = settings();
+
+        press(
+            &mut settings,
+            &mut navigator,
+            &[Button::Validate, Button::Next, Button::Back],
+        );
+
+        assert_eq!(settings.volume, 2);
+        assert_eq!(rows(&settings, &navigator).1, ">Volume         ");
+    }
+
+    #[test]
+    fn submenus_keep_the_selection_of_their_parent() {
+        let (mut settings, mut navigator) = settings();
+
+        press(
+            &mut settings,
+            &mut navigator,
+            &[Button::Next, Button::Validate, Button::Validate],
+        );
+        press(&mut settings, &mut navigator, &[Button::Next; 5]);
+        press(&mut settings, &mut navigator, &[Button::Validate]);
+        assert_eq!(settings.red, 64);
+
+        // Select "Retour"
+        press(
+            &mut settings,
+            &mut navigator,
+            &[Button::Next, Button::Validate],
+        );
+        assert_eq!(navigator.menu(), Menu::Root);
+        assert_eq!(rows(&settings, &navigator).1, ">Couleur        ");
+    }
+
+    #[test]
+    fn confirmed_actions() {
+        let (mut settings, mut navigator) = settings();
+        press(&mut settings, &mut navigator, &[Button::Next, Button::Next]);
+
+        // Defaults to "Non"
+        assert_eq!(
+            navigator.handle(&mut settings, Button::Validate),
+            Event::None
+        );
+        assert!(navigator.is_confirming());
+        assert_eq!(
+            rows(&settings, &navigator),
+            ("Réinitialiser   ".into(), ">Non    Oui     ".into())
+        );
+        assert_eq!(
+            navigator.handle(&mut settings, Button::Validate),
+            Event::None
+        );
+
+        press(
+            &mut settings,
+            &mut navigator,
+            &[Button::Validate, Button::Next],
+        );
+        assert_eq!(rows(&settings, &navigator).1, " Non   >Oui     ");
+        assert_eq!(
+            navigator.handle(&mut settings, Button::Validate),
+            Event::Action(Action::Reset)
+        );
+        assert!(!navigator.is_confirming());
+    }
+
+    #[test]
+    fn unconfirmed_action_and_exit() {
+        let (mut settings, mut navigator) = settings();
+        press(&mut settings, &mut navigator, &[Button::Next; 3]);
+
+        assert_eq!(
+            navigator.handle(&mut settings, Button::Validate),
+            Event::Action(Action::Save)
+        );
+        assert_eq!(navigator.handle(&mut settings, Button::Back), Event::Exit);
+        // The menu starts over from its first item
+        assert_eq!(navigator.selected(&settings), 0);
+    }
+
+    #[test]
+    fn fixed_labels_can_be_translated() {
+        let (mut settings, mut navigator) = settings();
+        settings.english = true;
+
+        press(&mut settings, &mut navigator, &[Button::Next; 4]);
+        assert_eq!(rows(&settings, &navigator).1, ">Back           ");
+        press(
+            &mut settings,
+            &mut navigator,
+            &[Button::Next, Button::Next, Button::Next, Button::Validate],
+        );
+
+        assert!(navigator.is_confirming());
+        assert_eq!(rows(&settings, &navigator).1, ">No    Yes      ");
+    }
+
+    #[test]
+    fn full_labels_are_cut_to_the_row() {
+        let (mut settings, mut navigator) = settings();
+        settings.long_labels = true;
+
+        press(&mut settings, &mut navigator, &[Button::Next; 4]);
+        assert_eq!(rows(&settings, &navigator).1, ">rrrrrrrrrrrrrrr");
+        press(&mut settings, &mut navigator, &[Button::Next; 3]);
+        press(&mut settings, &mut navigator, &[Button::Validate]);
+        assert_eq!(rows(&settings, &navigator).1, ">nnnnnnnnnnnnnnn");
+    }
+}
@@ -0,0 +1,281 @@
+//! DCF77 time signal, from a receiver module on a GPIO.
+//!
+//! The transmitter near Frankfurt lowers its carrier at the start of every
+//! second, for 100 ms for a 0 and 200 ms for a 1, except on the 59th second:
+//! the missing pulse marks the minute. The 59 bits of a minute give the
+//! German local time of the next one, with parity bits on the minutes, the
+//! hours and the date. A decoded time is only trusted once the next minute
+//! agrees with it. The British MSF signal sends some seconds as two pulses
+//! and is not decoded here.
+
+use clock::DateTime;
+
+// Accepted widths of the pulses and gaps between their starts, receivers are
+// not accurate.
+const SPIKE_MS: u32 = 40;
+const ZERO_MS: core::ops::RangeInclusive<u32> = 40..=140;
+const ONE_MS: core::ops::RangeInclusive<u32> = 150..=250;
+const SECOND_MS: core::ops::RangeInclusive<u64> = 900..=1100;
+const MINUTE_MARK_MS: core::ops::RangeInclusive<u64> = 1900..=2100;
+
+const MINUTE_BITS: u8 = 59;
+
+/// Time received at the start of a minute.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DcfTime {
+    /// German local time.
+    pub datetime: DateTime,
+    /// Central European Summer Time, UTC+2, instead of UTC+1.
+    pub summer_time: bool,
+    /// Start of the minute, on the clock of the pulses.
+    pub at_ms: u64,
+}
+
+impl DcfTime {
+    /// UTC time, from the offset sent along.
+    pub fn utc(&self) -> DateTime {
+        let offset_sec = if self.summer_time { 2 * 3600 } else { 3600 };
+        DateTime::from_timestamp(self.datetime.timestamp() - offset_sec)
+    }
+}
+
+pub struct Dcf77Decoder {
+    // Bit `n` was received on second `n`
+    bits: u64,
+    // Bits received since the last minute mark, `None` until one is seen
+    count: Option<u8>,
+    last_start_ms: Option<u64>,
+    pulse_start_ms: Option<u64>,
+    previous: Option<DcfTime>,
+}
+
+impl Dcf77Decoder {
+    pub fn new() -> Self {
+        Self {
+            bits: 0,
+            count: None,
+            last_start_ms: None,
+            pulse_start_ms: None,
+            previous: None,
+        }
+    }
+
+    /// Feeds an edge of the receiver output, `active` during the pulses.
+    pub fn edge(&mut self, active: bool, at_ms: u64) -> Option<DcfTime> {
+        if active {
+            self.pulse_start_ms = Some(at_ms);
+            None
+        } else {
+            let start_ms = self.pulse_start_ms.take()?;
+            self.pulse(start_ms, (at_ms - start_ms) as u32)
+        }
+    }
+
+    /// Feeds a pulse, returns the time once a minute has been confirmed.
+    pub fn pulse(&mut self, start_ms: u64, width_ms: u32) -> Option<DcfTime> {
+        // Interferences, the real pulse goes on
+        if width_ms < SPIKE_MS {
+            return None;
+        }
+        let bit = if ZERO_MS.contains(&width_ms) {
+            0
+        } else if ONE_MS.contains(&width_ms) {
+            1
+        } else {
+            self.lose_sync(start_ms);
+            return None;
+        };
+
+        let gap_ms = start_ms - self.last_start_ms.unwrap_or(start_ms);
+        self.last_start_ms = Some(start_ms);
+        if SECOND_MS.contains(&gap_ms) {
+            match self.count {
+                Some(count) if count < MINUTE_BITS => {
+                    self.bits |= bit << count;
+                    self.count = Some(count + 1);
+                }
+                _ => self.count = None,
+            }
+            None
+        } else if MINUTE_MARK_MS.contains(&gap_ms) {
+            let received = match self.count {
+                Some(MINUTE_BITS) => decode(self.bits, start_ms),
+                _ => None,
+            };
+            // This pulse is the second 0 of the new minute
+            self.bits = bit;
+            self.count = Some(1);
+
+            let previous = self.previous;
+            self.previous = received;
+            match (previous, received) {
+                (Some(previous), Some(received))
+                    if received.datetime.timestamp() == previous.datetime.timestamp() + 60 =>
+                {
+                    Some(received)
+                }
+                _ => None,
+            }
+        } else {
+            self.lose_sync(start_ms);
+            None
+        }
+    }
+
+    fn lose_sync(&mut self, start_ms: u64) {
+        self.count = None;
+        self.previous = None;
+        self.last_start_ms = Some(start_ms);
+    }
+}
+
+impl Default for Dcf77Decoder {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn field(bits: u64, start: u8, length: u8) -> u64 {
+    (bits >> start) & ((1 << length) - 1)
+}
+
+fn has_even_parity(bits: u64, start: u8, length: u8) -> bool {
+    field(bits, start, length).count_ones().is_multiple_of(2)
+}
+
+// Units on 4 bits then tens
+fn bcd(bits: u64, start: u8, length: u8) -> Option<u8> {
+    let value = field(bits, start, length) as u8;
+    let (tens, units) = (value >> 4, value & 0x0F);
+    if units > 9 {
+        return None;
+    }
+    Some(tens * 10 + units)
+}
+
+fn decode(bits: u64, at_ms: u64) -> Option<DcfTime> {
+    // Second 0 is always 0 and second 20 always 1
+    if field(bits, 0, 1) != 0 || field(bits, 20, 1) != 1 {
+        return None;
+    }
+    // Summer or winter time, exactly one of them
+    let summer_time = match field(bits, 17, 2) {
+        0b01 => true,
+        0b10 => false,
+        _ => return None,
+    };
+    if !has_even_parity(bits, 21, 8)
+        || !has_even_parity(bits, 29, 7)
+        || !has_even_parity(bits, 36, 23)
+    {
+        return None;
+    }
+    let datetime = DateTime {
+        year: 2000 + bcd(bits, 50, 8)? as u16,
+        month: bcd(bits, 45, 5)?,
+        day: bcd(bits, 36, 6)?,
+        hour: bcd(bits, 29, 6)?,
+        minute: bcd(bits, 21, 7)?,
+        second: 0,
+    };
+    // From 1 (Monday) to 7 (Sunday)
+    let weekday = field(bits, 42, 3) as u8;
+    if !datetime.is_valid() || weekday % 7 != datetime.day_of_week() {
+        return None;
+    }
+    Some(DcfTime {
+        datetime,
+        summer_time,
+        at_ms,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::dcf77::{Dcf77Decoder, DcfTime};
+    use clock::DateTime;
+
+    const TRACE: &str = include_str!("../testdata/dcf77_2026-10-19.txt");
+
+    fn pulses() -> Vec<(u64, u32)> {
+        TRACE
+            .lines()
+            .filter(|line| !line.starts_with('#'))
+            .map(|line| {
+                let mut values = line.split_whitespace();
+                let start = values.next().unwrap().parse().unwrap();
+                let width = values.next().unwrap().parse().unwrap();
+                (start, width)
+            })
+            .collect()
+    }
+
+    fn decode_all(pulses: &[(u64, u32)]) -> Vec<DcfTime> {
+        let mut decoder = Dcf77Decoder::new();
+        pulses
+            .iter()
+            .filter_map(|&(start, width)| decoder.pulse(start, width))
+            .collect()
+    }
+
+    #[test]
+    fn recorded_trace_is_decoded_on_the_second_full_minute() {
+        let times = decode_all(&pulses());
+
+        assert_eq!(
+            times,
+            vec![DcfTime {
+                datetime: DateTime {
+                    year: 2026,
+                    month: 10,
+                    day: 19,
+                    hour: 18,
+                    minute: 7,
+                    second: 0,
+                },
+                summer_time: true,
+                at_ms: 145005,
+            }]
+        );
+    }
+
+    #[test]
+    fn edges_give_the_same_pulses() {
+        let mut decoder = Dcf77Decoder::new();
+        let mut times = Vec::new();
+        for (start, width) in pulses() {
+            decoder.edge(true, start);
+            times.extend(decoder.edge(false, start + width as u64));
+        }
+
+        assert_eq!(times.len(), 1);
+        assert_eq!(times[0].datetime.minute, 7);
+        assert_eq!(times[0].utc().hour, 16);
+    }
+
+    #[test]
+    fn flipped_bit_fails_the_parity() {
+        let mut pulses = pulses();
+        // A minute bit of the last frame, 18:06:22
+        let index = pulses
+            .iter()
+            .position(|&(start, _)| start > 106_900)
+            .unwrap();
+        pulses[index].1 = if pulses[index].1 > 145 { 100 } else { 200 };
+
+        assert_eq!(decode_all(&pulses), vec![]);
+    }
+
+    #[test]
+    fn missing_pulse_loses_the_minute() {
+        let mut pulses = pulses();
+        // 18:06:10
+        let index = pulses
+            .iter()
+            .position(|&(start, _)| start > 94_900)
+            .unwrap();
+        pulses.remove(index);
+
+        assert_eq!(decode_all(&pulses), vec![]);
+    }
+}
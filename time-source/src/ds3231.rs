@@ -0,0 +1,143 @@
+//! Maxim DS3231, temperature compensated RTC.
+
+use clock::DateTime;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::{from_bcd, to_bcd, ExternalClock};
+
+pub const ADDRESS: u8 = 0x68;
+
+const REG_SECONDS: u8 = 0x00;
+const REG_STATUS: u8 = 0x0F;
+// Set when the oscillator stopped, e.g. without power nor battery
+const OSCILLATOR_STOP_FLAG: u8 = 0x80;
+const HOURS_12: u8 = 0x40;
+const PM: u8 = 0x20;
+const CENTURY: u8 = 0x80;
+
+pub struct Ds3231 {
+    address: u8,
+}
+
+impl Ds3231 {
+    pub fn new() -> Self {
+        Self { address: ADDRESS }
+    }
+}
+
+impl Default for Ds3231 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExternalClock for Ds3231 {
+    fn read_time<I: WriteRead>(&mut self, i2c: &mut I) -> Result<Option<DateTime>, I::Error> {
+        let mut status = [0];
+        i2c.write_read(self.address, &[REG_STATUS], &mut status)?;
+        if status[0] & OSCILLATOR_STOP_FLAG != 0 {
+            return Ok(None);
+        }
+
+        let mut registers = [0; 7];
+        i2c.write_read(self.address, &[REG_SECONDS], &mut registers)?;
+        // The time may have been set in 12 hours mode by another program
+        let hour = if registers[2] & HOURS_12 != 0 {
+            from_bcd(registers[2] & 0x1F) % 12 + if registers[2] & PM != 0 { 12 } else { 0 }
+        } else {
+            from_bcd(registers[2] & 0x3F)
+        };
+        let century = if registers[5] & CENTURY != 0 { 100 } else { 0 };
+        let datetime = DateTime {
+            year: 2000 + century + from_bcd(registers[6]) as u16,
+            month: from_bcd(registers[5] & 0x1F),
+            day: from_bcd(registers[4] & 0x3F),
+            hour,
+            minute: from_bcd(registers[1] & 0x7F),
+            second: from_bcd(registers[0] & 0x7F),
+        };
+        Ok(Some(datetime).filter(DateTime::is_valid))
+    }
+
+    fn set_time<I: Write>(&mut self, i2c: &mut I, datetime: &DateTime) -> Result<(), I::Error> {
+        let century = if datetime.year >= 2100 { CENTURY } else { 0 };
+        i2c.write(
+            self.address,
+            &[
+                REG_SECONDS,
+                to_bcd(datetime.second),
+                to_bcd(datetime.minute),
+                // 24 hours mode
+                to_bcd(datetime.hour),
+                // From 1 to 7
+                datetime.day_of_week() + 1,
+                to_bcd(datetime.day),
+                to_bcd(datetime.month) | century,
+                to_bcd((datetime.year % 100) as u8),
+            ],
+        )?;
+        // Clears the oscillator stop flag, the 32 kHz output is not used
+        i2c.write(self.address, &[REG_STATUS, 0])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::ds3231::{Ds3231, ADDRESS};
+    use crate::mock::MockRegisters;
+    use crate::ExternalClock;
+    use clock::DateTime;
+
+    const DATETIME: DateTime = DateTime {
+        year: 2026,
+        month: 10,
+        day: 19,
+        hour: 18,
+        minute: 5,
+        second: 42,
+    };
+
+    #[test]
+    fn set_time_is_read_back() {
+        let mut i2c = MockRegisters::new(ADDRESS);
+        i2c.registers[0x0F] = 0x88;
+        let mut rtc = Ds3231::new();
+
+        rtc.set_time(&mut i2c, &DATETIME).unwrap();
+
+        assert_eq!(
+            &i2c.registers[..7],
+            &[0x42, 0x05, 0x18, 0x02, 0x19, 0x10, 0x26]
+        );
+        assert_eq!(rtc.read_time(&mut i2c), Ok(Some(DATETIME)));
+    }
+
+    #[test]
+    fn stopped_oscillator_lost_the_time() {
+        let mut i2c = MockRegisters::new(ADDRESS);
+        let mut rtc = Ds3231::new();
+        rtc.set_time(&mut i2c, &DATETIME).unwrap();
+
+        i2c.registers[0x0F] = 0x80;
+
+        assert_eq!(rtc.read_time(&mut i2c), Ok(None));
+    }
+
+    #[test]
+    fn twelve_hours_mode_and_garbage() {
+        let mut i2c = MockRegisters::new(ADDRESS);
+        let mut rtc = Ds3231::new();
+        rtc.set_time(&mut i2c, &DATETIME).unwrap();
+
+        // 6 PM
+        i2c.registers[2] = 0x40 | 0x20 | 0x06;
+        assert_eq!(rtc.read_time(&mut i2c), Ok(Some(DATETIME)));
+        // 12 AM
+        i2c.registers[2] = 0x40 | 0x12;
+        assert_eq!(rtc.read_time(&mut i2c).unwrap().unwrap().hour, 0);
+        // 31 November does not exist
+        i2c.registers[4] = 0x31;
+        i2c.registers[5] = 0x11;
+        assert_eq!(rtc.read_time(&mut i2c), Ok(None));
+    }
+}
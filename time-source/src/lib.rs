@@ -0,0 +1,41 @@
+#![cfg_attr(not(test), no_std)]
+
+//! Sources of the current time other than the user.
+//!
+//! The RP2040 RTC forgets the time on every power cut. The battery-backed
+//! clocks of this crate keep it, and tell when they have lost it too so that
+//! the firmware only asks the user then. They talk on an I²C bus shared with
+//! other devices, which is given to each call. `dcf77` decodes the time
+//! broadcast by radio, `nmea` the one of a GPS receiver.
+
+pub mod dcf77;
+pub mod ds3231;
+pub mod nmea;
+pub mod pcf8563;
+
+pub use dcf77::{Dcf77Decoder, DcfTime};
+pub use ds3231::Ds3231;
+pub use nmea::{NmeaError, NmeaReader};
+pub use pcf8563::Pcf8563;
+
+use clock::DateTime;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+/// Battery-backed clock on an I²C bus.
+pub trait ExternalClock {
+    /// Reads the time, `None` if the clock lost it, e.g. with a dead battery.
+    fn read_time<I: WriteRead>(&mut self, i2c: &mut I) -> Result<Option<DateTime>, I::Error>;
+    /// Sets the time, which is then valid again.
+    fn set_time<I: Write>(&mut self, i2c: &mut I, datetime: &DateTime) -> Result<(), I::Error>;
+}
+
+fn from_bcd(value: u8) -> u8 {
+    (value >> 4) * 10 + (value & 0x0F)
+}
+
+fn to_bcd(value: u8) -> u8 {
+    ((value / 10) << 4) | (value % 10)
+}
+
+#[cfg(test)]
+mod mock;
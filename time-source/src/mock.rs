@@ -0,0 +1,47 @@
+//! Register file of an I²C device, for the tests.
+
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+#[derive(Debug, PartialEq)]
+pub struct Nack;
+
+pub struct MockRegisters {
+    address: u8,
+    pub registers: [u8; 16],
+}
+
+impl MockRegisters {
+    pub fn new(address: u8) -> Self {
+        Self {
+            address,
+            registers: [0; 16],
+        }
+    }
+}
+
+impl Write for MockRegisters {
+    type Error = Nack;
+
+    // The first byte selects the register, the next ones are written from it
+    fn write(&mut self, address: u8, bytes: &[u8]) -> Result<(), Nack> {
+        if address != self.address || bytes.is_empty() {
+            return Err(Nack);
+        }
+        let start = bytes[0] as usize;
+        self.registers[start..start + bytes.len() - 1].copy_from_slice(&bytes[1..]);
+        Ok(())
+    }
+}
+
+impl WriteRead for MockRegisters {
+    type Error = Nack;
+
+    fn write_read(&mut self, address: u8, bytes: &[u8], buffer: &mut [u8]) -> Result<(), Nack> {
+        if address != self.address || bytes.len() != 1 {
+            return Err(Nack);
+        }
+        let start = bytes[0] as usize;
+        buffer.copy_from_slice(&self.registers[start..start + buffer.len()]);
+        Ok(())
+    }
+}
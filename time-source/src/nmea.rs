@@ -0,0 +1,213 @@
+//! NMEA 0183 sentences of a GPS receiver, e.g. read from a UART.
+//!
+//! Receivers send their state once a second, as lines of text starting with
+//! `$` and ending with a checksum. The `RMC` and `ZDA` sentences give the UTC
+//! date and time, whatever the talker (`GP`, `GN`...); the others are skipped.
+//! `RMC` also tells whether the receiver has a fix, `ZDA` has empty fields
+//! until the time is known.
+
+use arrayvec::ArrayVec;
+use clock::DateTime;
+
+/// Longest sentence allowed by the standard, from `$` to the checksum.
+pub const MAX_SENTENCE_LENGTH: usize = 82;
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum NmeaError {
+    MissingChecksum,
+    BadChecksum,
+    Malformed,
+    /// The receiver does not know the time yet.
+    NoFix,
+    TooLong,
+}
+
+/// Parses a sentence without its line ending, `None` for sentences without
+/// the time.
+pub fn parse_sentence(sentence: &str) -> Result<Option<DateTime>, NmeaError> {
+    let body = sentence.strip_prefix('$').ok_or(NmeaError::Malformed)?;
+    let (body, checksum) = body.split_once('*').ok_or(NmeaError::MissingChecksum)?;
+    let checksum = u8::from_str_radix(checksum, 16).map_err(|_| NmeaError::MissingChecksum)?;
+    if body.bytes().fold(0, |sum, byte| sum ^ byte) != checksum {
+        return Err(NmeaError::BadChecksum);
+    }
+
+    let mut fields = body.split(',');
+    let kind = fields.next().ok_or(NmeaError::Malformed)?;
+    let datetime = match kind.get(2..) {
+        // hhmmss.ss,A,llll.ll,a,yyyyy.yy,a,x.x,x.x,ddmmyy,...
+        Some("RMC") => {
+            let time = fields.next().ok_or(NmeaError::Malformed)?;
+            if fields.next() != Some("A") {
+                return Err(NmeaError::NoFix);
+            }
+            let date = fields.nth(6).ok_or(NmeaError::Malformed)?;
+            if time.is_empty() || date.is_empty() {
+                return Err(NmeaError::NoFix);
+            }
+            DateTime {
+                year: 2000 + number(date, 4..6)?,
+                month: number(date, 2..4)? as u8,
+                day: number(date, 0..2)? as u8,
+                hour: number(time, 0..2)? as u8,
+                minute: number(time, 2..4)? as u8,
+                second: number(time, 4..6)? as u8,
+            }
+        }
+        // hhmmss.ss,dd,mm,yyyy,zone hours,zone minutes
+        Some("ZDA") => {
+            let time = fields.next().ok_or(NmeaError::Malformed)?;
+            let day = fields.next().ok_or(NmeaError::Malformed)?;
+            let month = fields.next().ok_or(NmeaError::Malformed)?;
+            let year = fields.next().ok_or(NmeaError::Malformed)?;
+            if time.is_empty() || year.is_empty() {
+                return Err(NmeaError::NoFix);
+            }
+            DateTime {
+                year: number(year, 0..year.len())?,
+                month: number(month, 0..month.len())? as u8,
+                day: number(day, 0..day.len())? as u8,
+                hour: number(time, 0..2)? as u8,
+                minute: number(time, 2..4)? as u8,
+                second: number(time, 4..6)? as u8,
+            }
+        }
+        _ => return Ok(None),
+    };
+    if !datetime.is_valid() {
+        return Err(NmeaError::Malformed);
+    }
+    Ok(Some(datetime))
+}
+
+fn number(field: &str, range: core::ops::Range<usize>) -> Result<u16, NmeaError> {
+    let digits = field.get(range).ok_or(NmeaError::Malformed)?;
+    if digits.is_empty() || !digits.bytes().all(|byte| byte.is_ascii_digit()) {
+        return Err(NmeaError::Malformed);
+    }
+    digits.parse().map_err(|_| NmeaError::Malformed)
+}
+
+/// Splits the bytes received into sentences.
+pub struct NmeaReader {
+    line: ArrayVec<u8, MAX_SENTENCE_LENGTH>,
+    // Inside a sentence, until its line ending
+    started: bool,
+    too_long: bool,
+}
+
+impl NmeaReader {
+    pub fn new() -> Self {
+        Self {
+            line: ArrayVec::new(),
+            started: false,
+            too_long: false,
+        }
+    }
+
+    /// Feeds a byte, returns the time or the error of the sentence it ends.
+    pub fn push(&mut self, byte: u8) -> Option<Result<DateTime, NmeaError>> {
+        match byte {
+            // A sentence cut short is dropped when the next one starts
+            b'$' => {
+                self.line.clear();
+                self.started = true;
+                self.too_long = false;
+                self.line.push(byte);
+                None
+            }
+            b'\r' => None,
+            b'\n' if self.started => {
+                self.started = false;
+                if self.too_long {
+                    return Some(Err(NmeaError::TooLong));
+                }
+                let sentence = core::str::from_utf8(&self.line).map_err(|_| NmeaError::Malformed);
+                sentence.and_then(parse_sentence).transpose()
+            }
+            _ if self.started => {
+                if self.line.try_push(byte).is_err() {
+                    self.too_long = true;
+                }
+                None
+            }
+            _ => None,
+        }
+    }
+}
+
+impl Default for NmeaReader {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::nmea::{parse_sentence, NmeaError, NmeaReader};
+    use clock::DateTime;
+
+    const LOG: &str = include_str!("../testdata/nmea_neo6m.log");
+
+    fn read(bytes: &[u8]) -> Vec<Result<DateTime, NmeaError>> {
+        let mut reader = NmeaReader::new();
+        bytes.iter().filter_map(|&byte| reader.push(byte)).collect()
+    }
+
+    fn at(year: u16, month: u8, day: u8, hour: u8, minute: u8, second: u8) -> DateTime {
+        DateTime {
+            year,
+            month,
+            day,
+            hour,
+            minute,
+            second,
+        }
+    }
+
+    #[test]
+    fn captured_log_gives_the_utc_time_once_known() {
+        assert_eq!(
+            read(LOG.as_bytes()),
+            vec![
+                Err(NmeaError::NoFix),
+                Err(NmeaError::NoFix),
+                Ok(at(2026, 10, 19, 16, 4, 55)),
+                Err(NmeaError::BadChecksum),
+                Ok(at(2026, 10, 19, 16, 4, 57)),
+                Err(NmeaError::MissingChecksum),
+                Ok(at(2026, 10, 19, 16, 4, 57)),
+                Ok(at(2026, 12, 31, 23, 59, 59)),
+            ]
+        );
+    }
+
+    #[test]
+    fn overlong_line_is_dropped() {
+        let mut line = vec![b'$'; 1];
+        line.extend([b'A'; 100]);
+        line.extend(b"\r\n$GPZDA,160457.00,19,10,2026,00,00*68\r\n");
+
+        assert_eq!(
+            read(&line),
+            vec![Err(NmeaError::TooLong), Ok(at(2026, 10, 19, 16, 4, 57))]
+        );
+    }
+
+    #[test]
+    fn malformed_fields() {
+        assert_eq!(
+            parse_sentence("$GPZDA,1604,19,10,2026,00,00*44"),
+            Err(NmeaError::Malformed)
+        );
+        assert_eq!(
+            parse_sentence("$GPZDA,160457.00,31,02,2026,00,00*61"),
+            Err(NmeaError::Malformed)
+        );
+        assert_eq!(parse_sentence("$GPVTG,,,,,,,,,N*30"), Ok(None));
+        assert_eq!(
+            parse_sentence("GPVTG,,,,,,,,,N*30"),
+            Err(NmeaError::Malformed)
+        );
+    }
+}
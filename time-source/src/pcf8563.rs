@@ -0,0 +1,123 @@
+//! NXP PCF8563, low power RTC.
+
+use clock::DateTime;
+use embedded_hal::blocking::i2c::{Write, WriteRead};
+
+use crate::{from_bcd, to_bcd, ExternalClock};
+
+pub const ADDRESS: u8 = 0x51;
+
+const REG_CONTROL_STATUS_1: u8 = 0x00;
+const REG_SECONDS: u8 = 0x02;
+// Set in the seconds when the voltage got too low for the clock to run
+const VOLTAGE_LOW: u8 = 0x80;
+const CENTURY: u8 = 0x80;
+
+pub struct Pcf8563 {
+    address: u8,
+}
+
+impl Pcf8563 {
+    pub fn new() -> Self {
+        Self { address: ADDRESS }
+    }
+}
+
+impl Default for Pcf8563 {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ExternalClock for Pcf8563 {
+    fn read_time<I: WriteRead>(&mut self, i2c: &mut I) -> Result<Option<DateTime>, I::Error> {
+        let mut registers = [0; 7];
+        i2c.write_read(self.address, &[REG_SECONDS], &mut registers)?;
+        if registers[0] & VOLTAGE_LOW != 0 {
+            return Ok(None);
+        }
+
+        let century = if registers[5] & CENTURY != 0 { 100 } else { 0 };
+        let datetime = DateTime {
+            year: 2000 + century + from_bcd(registers[6]) as u16,
+            month: from_bcd(registers[5] & 0x1F),
+            day: from_bcd(registers[3] & 0x3F),
+            hour: from_bcd(registers[2] & 0x3F),
+            minute: from_bcd(registers[1] & 0x7F),
+            second: from_bcd(registers[0] & 0x7F),
+        };
+        Ok(Some(datetime).filter(DateTime::is_valid))
+    }
+
+    fn set_time<I: Write>(&mut self, i2c: &mut I, datetime: &DateTime) -> Result<(), I::Error> {
+        // Makes sure the clock runs
+        i2c.write(self.address, &[REG_CONTROL_STATUS_1, 0])?;
+        let century = if datetime.year >= 2100 { CENTURY } else { 0 };
+        i2c.write(
+            self.address,
+            &[
+                REG_SECONDS,
+                // Also clears the low voltage flag
+                to_bcd(datetime.second),
+                to_bcd(datetime.minute),
+                to_bcd(datetime.hour),
+                to_bcd(datetime.day),
+                // From 0 (Sunday) to 6
+                datetime.day_of_week(),
+                to_bcd(datetime.month) | century,
+                to_bcd((datetime.year % 100) as u8),
+            ],
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::mock::{MockRegisters, Nack};
+    use crate::pcf8563::{Pcf8563, ADDRESS};
+    use crate::ExternalClock;
+    use clock::DateTime;
+
+    const DATETIME: DateTime = DateTime {
+        year: 2024,
+        month: 2,
+        day: 29,
+        hour: 7,
+        minute: 30,
+        second: 0,
+    };
+
+    #[test]
+    fn set_time_is_read_back() {
+        let mut i2c = MockRegisters::new(ADDRESS);
+        i2c.registers[0] = 0x20;
+        let mut rtc = Pcf8563::new();
+
+        rtc.set_time(&mut i2c, &DATETIME).unwrap();
+
+        assert_eq!(i2c.registers[0], 0);
+        assert_eq!(
+            &i2c.registers[2..9],
+            &[0x00, 0x30, 0x07, 0x29, 0x04, 0x02, 0x24]
+        );
+        assert_eq!(rtc.read_time(&mut i2c), Ok(Some(DATETIME)));
+    }
+
+    #[test]
+    fn low_voltage_lost_the_time() {
+        let mut i2c = MockRegisters::new(ADDRESS);
+        let mut rtc = Pcf8563::new();
+        rtc.set_time(&mut i2c, &DATETIME).unwrap();
+
+        i2c.registers[2] |= 0x80;
+
+        assert_eq!(rtc.read_time(&mut i2c), Ok(None));
+    }
+
+    #[test]
+    fn missing_device_is_an_error() {
+        let mut i2c = MockRegisters::new(0x68);
+
+        assert_eq!(Pcf8563::new().read_time(&mut i2c), Err(Nack));
+    }
+}
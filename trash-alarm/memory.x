@@ -1,6 +1,7 @@
 MEMORY {
     BOOT2 : ORIGIN = 0x10000000, LENGTH = 0x100
-    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100
+    /* The last sector keeps the settings, see `storage.rs` */
+    FLASH : ORIGIN = 0x10000100, LENGTH = 2048K - 0x100 - 4K
     RAM   : ORIGIN = 0x20000000, LENGTH = 256K
 }
 
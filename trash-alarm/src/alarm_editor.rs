@@ -0,0 +1,359 @@
+use alarm::alarm_manager::AlarmManager;
+use alarm::{Alarm, WeeklyDate};
+use arrayvec::ArrayString;
+use buzzer::Buzzer;
+use callback::Stopper;
+use clock::entry::{Inactivity, ENTRY_TIMEOUT_MS};
+use core::convert::Infallible;
+use core::fmt::Write;
+use display::backlight::Rgb;
+use display::format::day_of_week_string;
+use display::{FrameBuffer, Message, TextDisplay};
+use input::ButtonEvent;
+use locale::{Text, LANGUAGE};
+use menu::{label, Button, Event, Item, Label, MenuModel, Navigator, ValueRange};
+
+use crate::buttons::GestureButton;
+use crate::callbacks::{CallbackBuzzerAndWriteText, CallbackError, CallbackWriteText};
+use crate::datetime::day_of_week_from_u8;
+use crate::inputs;
+
+/// Alarms of the firmware: a melody with a message, then a thank you message.
+pub type TrashAlarm<D, B, S> =
+    Alarm<CallbackBuzzerAndWriteText<D, B, S>, CallbackWriteText<D>, WeeklyDate>;
+
+/// Alarm description and the message shown while it rings.
+pub struct TextPreset {
+    pub description: Text,
+    pub message: Text,
+}
+
+pub const TEXT_PRESETS: [TextPreset; 5] = [
+    TextPreset {
+        description: Text::GreenBin,
+        message: Text::TakeOutGreenBin,
+    },
+    TextPreset {
+        description: Text::YellowBin,
+        message: Text::TakeOutYellowBin,
+    },
+    TextPreset {
+        description: Text::Glass,
+        message: Text::TakeOutGlass,
+    },
+    TextPreset {
+        description: Text::Compost,
+        message: Text::TakeOutCompost,
+    },
+    TextPreset {
+        description: Text::BulkyWaste,
+        message: Text::TakeOutBulkyWaste,
+    },
+];
+
+pub struct ColorPreset {
+    pub name: Text,
+    pub rgb: Rgb,
+}
+
+pub const COLOR_PRESETS: [ColorPreset; 6] = [
+    ColorPreset {
+        name: Text::Green,
+        rgb: (0, 255, 0),
+    },
+    ColorPreset {
+        name: Text::Yellow,
+        rgb: (255, 255, 0),
+    },
+    ColorPreset {
+        name: Text::Blue,
+        rgb: (0, 0, 255),
+    },
+    ColorPreset {
+        name: Text::Red,
+        rgb: (255, 0, 0),
+    },
+    ColorPreset {
+        name: Text::Brown,
+        rgb: (139, 69, 19),
+    },
+    ColorPreset {
+        name: Text::White,
+        rgb: (255, 255, 255),
+    },
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum EditorMenu {
+    Alarms,
+    Alarm(usize),
+}
+
+#[derive(Clone, Copy, PartialEq)]
+pub enum Setting {
+    Enabled,
+    DayOfWeek,
+    Hour,
+    Minute,
+    // In hours
+    Duration,
+    Message,
+    Color,
+}
+
+const SETTINGS: [(Setting, Text); 7] = [
+    (Setting::Enabled, Text::Enabled),
+    (Setting::DayOfWeek, Text::Weekday),
+    (Setting::Hour, Text::Hour),
+    (Setting::Minute, Text::Minute),
+    (Setting::Duration, Text::Duration),
+    (Setting::Message, Text::Message),
+    (Setting::Color, Text::Colour),
+];
+
+#[derive(Clone, Copy, PartialEq)]
+pub struct EditorField {
+    alarm: usize,
+    setting: Setting,
+}
+
+/// Menus listing the alarms of the manager and editing them in place.
+pub struct AlarmEditor<'a, D: TextDisplay, B: Buzzer, S: Stopper>
+where
+    CallbackError: From<D::Error> + From<B::Error> + From<S::Error>,
+{
+    alarms: &'a mut AlarmManager<TrashAlarm<D, B, S>>,
+}
+
+impl<'a, D: TextDisplay, B: Buzzer, S: Stopper> AlarmEditor<'a, D, B, S>
+where
+    CallbackError: From<D::Error> + From<B::Error> + From<S::Error>,
+{
+    pub fn new(alarms: &'a mut AlarmManager<TrashAlarm<D, B, S>>) -> Self {
+        Self { alarms }
+    }
+
+    fn alarm(&self, index: usize) -> &TrashAlarm<D, B, S> {
+        self.alarms.alarm(index).unwrap()
+    }
+}
+
+impl<'a, D: TextDisplay, B: Buzzer, S: Stopper> MenuModel for AlarmEditor<'a, D, B, S>
+where
+    CallbackError: From<D::Error> + From<B::Error> + From<S::Error>,
+{
+    type Menu = EditorMenu;
+    type Field = EditorField;
+    type Action = Infallible;
+
+    fn title(&self, menu: EditorMenu) -> Label {
+        match menu {
+            EditorMenu::Alarms => label(LANGUAGE.text(Text::Alarms)),
+            EditorMenu::Alarm(index) => label(self.alarm(index).description().as_str()),
+        }
+    }
+
+    fn item_count(&self, menu: EditorMenu) -> usize {
+        // Followed by "Retour"
+        match menu {
+            EditorMenu::Alarms => self.alarms.len() + 1,
+            EditorMenu::Alarm(_) => SETTINGS.len() + 1,
+        }
+    }
+
+    fn item(&self, menu: EditorMenu, index: usize) -> Item<EditorMenu, EditorField, Infallible> {
+        match menu {
+            EditorMenu::Alarms if index < self.alarms.len() => Item::Submenu {
+                label: label(self.alarm(index).description().as_str()),
+                menu: EditorMenu::Alarm(index),
+            },
+            EditorMenu::Alarm(alarm) if index < SETTINGS.len() => Item::Edit {
+                label: label(LANGUAGE.text(SETTINGS[index].1)),
+                field: EditorField {
+                    alarm,
+                    setting: SETTINGS[index].0,
+                },
+            },
+            _ => Item::Back,
+        }
+    }
+
+    fn value(&self, field: EditorField) -> u16 {
+        let alarm = self.alarm(field.alarm);
+        let date = alarm.date();
+        match field.setting {
+            Setting::Enabled => alarm.is_enabled() as u16,
+            Setting::DayOfWeek => date.day_of_week() as u16,
+            Setting::Hour => date.hour() as u16,
+            Setting::Minute => date.minute() as u16,
+            Setting::Duration => (alarm.total_duration_sec() / 3600) as u16,
+            Setting::Message => TEXT_PRESETS
+                .iter()
+                .position(|preset| {
+                    LANGUAGE.text(preset.description) == alarm.description().as_str()
+                })
+                .unwrap_or(0) as u16,
+            Setting::Color => COLOR_PRESETS
+                .iter()
+                .position(|preset| Some(preset.rgb) == alarm.callback().animation().color())
+                .unwrap_or(0) as u16,
+        }
+    }
+
+    fn range(&self, field: EditorField) -> ValueRange {
+        match field.setting {
+            Setting::Enabled => ValueRange::new(0, 1),
+            Setting::DayOfWeek => ValueRange::new(0, 6),
+            Setting::Hour => ValueRange::new(0, 23),
+            Setting::Minute => ValueRange::new(0, 55).with_step(5),
+            Setting::Duration => ValueRange::new(1, 24),
+            Setting::Message => ValueRange::new(0, TEXT_PRESETS.len() as u16 - 1),
+            Setting::Color => ValueRange::new(0, COLOR_PRESETS.len() as u16 - 1),
+        }
+    }
+
+    fn set_value(&mut self, field: EditorField, value: u16) {
+        let alarm = match self.alarms.alarm_mut(field.alarm) {
+            Some(alarm) => alarm,
+            None => return,
+        };
+        let date = *alarm.date();
+        let (day_of_week, hour, minute, second) = (
+            date.day_of_week(),
+            date.hour(),
+            date.minute(),
+            date.second(),
+        );
+        match field.setting {
+            Setting::Enabled => alarm.set_enabled(value != 0),
+            Setting::DayOfWeek => alarm.set_date(WeeklyDate::new(
+                day_of_week_from_u8(value as u8),
+                hour,
+                minute,
+                second,
+            )),
+            Setting::Hour => {
+                alarm.set_date(WeeklyDate::new(day_of_week, value as u32, minute, second))
+            }
+            Setting::Minute => {
+                alarm.set_date(WeeklyDate::new(day_of_week, hour, value as u32, second))
+            }
+            Setting::Duration => alarm.set_total_duration_sec(value as u32 * 3600),
+            Setting::Message => {
+                let preset = &TEXT_PRESETS[value as usize];
+                alarm
+                    .set_description(ArrayString::from(LANGUAGE.text(preset.description)).unwrap());
+                alarm
+                    .callback_mut()
+                    .set_text(Message::from(LANGUAGE.text(preset.message)).unwrap());
+            }
+            Setting::Color => {
+                let callback = alarm.callback_mut();
+                let animation = callback.animation();
+                callback.set_animation(animation.with_color(COLOR_PRESETS[value as usize].rgb));
+            }
+        }
+    }
+
+    fn format_value(&self, field: EditorField, value: u16) -> Label {
+        let mut text = Label::new();
+        match field.setting {
+            Setting::Enabled => {
+                text.push_str(LANGUAGE.text(if value != 0 { Text::Yes } else { Text::No }))
+            }
+            Setting::DayOfWeek => text.push_str(day_of_week_string(value as u8).trim_end()),
+            Setting::Hour => write!(text, "{:0>2} h", value).unwrap(),
+            Setting::Minute => write!(text, "{:0>2} min", value).unwrap(),
+            Setting::Duration => write!(text, "{} h", value).unwrap(),
+            Setting::Message => {
+                text.push_str(LANGUAGE.text(TEXT_PRESETS[value as usize].description))
+            }
+            Setting::Color => text.push_str(LANGUAGE.text(COLOR_PRESETS[value as usize].name)),
+        }
+        text
+    }
+
+    fn back_label(&self) -> Label {
+        label(LANGUAGE.text(Text::Back))
+    }
+
+    fn yes_label(&self) -> Label {
+        label(LANGUAGE.text(Text::Yes))
+    }
+
+    fn no_label(&self) -> Label {
+        label(LANGUAGE.text(Text::No))
+    }
+}
+
+/// Runs the alarm settings screen until the user leaves it, or after a minute
+/// without any button used.
+///
+/// A click on `next_button` moves and increments, holding it repeats. A click on
+/// `validate_button` validates, a long press goes back.
+pub fn edit_alarms<D, B, S, L>(
+    alarms: &mut AlarmManager<TrashAlarm<D, B, S>>,
+    frame: &mut FrameBuffer,
+    lcd: &mut L,
+    next_button: &mut GestureButton,
+    validate_button: &mut GestureButton,
+) -> Result<(), L::Error>
+where
+    D: TextDisplay,
+    B: Buzzer,
+    S: Stopper,
+    L: TextDisplay,
+    CallbackError: From<D::Error> + From<B::Error> + From<S::Error>,
+{
+    let mut editor = AlarmEditor::new(alarms);
+    let mut navigator = Navigator::new(EditorMenu::Alarms);
+    // The button opening the settings may still be pressed
+    next_button.wait_for_release();
+    validate_button.wait_for_release();
+    let mut inactivity = Inactivity::new(ENTRY_TIMEOUT_MS, inputs::now_ms());
+    loop {
+        navigator.render(&editor, frame).ok();
+        frame.flush(lcd)?;
+
+        let button = match wait_for_button(next_button, validate_button, &mut inactivity) {
+            Some(button) => button,
+            // Left open by mistake, the values validated so far are kept
+            None => return Ok(()),
+        };
+        if navigator.handle(&mut editor, button) == Event::Exit {
+            return Ok(());
+        }
+    }
+}
+
+/// Next button used, `None` once `inactivity` expired.
+fn wait_for_button(
+    next_button: &mut GestureButton,
+    validate_button: &mut GestureButton,
+    inactivity: &mut Inactivity,
+) -> Option<Button> {
+    loop {
+        for event in next_button.poll() {
+            inactivity.touch(inputs::now_ms());
+            if let ButtonEvent::ShortPress | ButtonEvent::Repeat = event {
+                return Some(Button::Next);
+            }
+        }
+        for event in validate_button.poll() {
+            inactivity.touch(inputs::now_ms());
+            match event {
+                ButtonEvent::ShortPress | ButtonEvent::DoublePress => {
+                    return Some(Button::Validate)
+                }
+                ButtonEvent::LongPress => return Some(Button::Back),
+                _ => {}
+            }
+        }
+        if inactivity.is_expired(inputs::now_ms()) {
+            return None;
+        }
+        // We sleep until the next user input or the timeout.
+        inputs::wake_at(inactivity.deadline_ms());
+        inputs::wait();
+    }
+}
@@ -0,0 +1,69 @@
+use input::{Events, GestureConfig, Gestures};
+
+use crate::inputs::{self, Source};
+
+/// Push button read through the gesture recognizer, from the edges caught by
+/// the interrupts.
+pub struct GestureButton {
+    source: Source,
+    gestures: Gestures,
+}
+
+impl GestureButton {
+    pub fn new(source: Source, config: GestureConfig) -> Self {
+        Self {
+            source,
+            gestures: Gestures::new(config),
+        }
+    }
+
+    /// Reads the pending edges of the button. Returns as soon as they give
+    /// events, the remaining edges are read by the next poll.
+    pub fn poll(&mut self) -> Events {
+        while let Some(edge) = inputs::next_edge(self.source) {
+            let events = self.gestures.edge(edge);
+            if !events.is_empty() {
+                return events;
+            }
+        }
+        self.gestures.tick(inputs::now_ms())
+    }
+
+    /// Debounced state as of the last poll.
+    pub fn is_pressed(&self) -> bool {
+        self.gestures.is_pressed()
+    }
+
+    /// Sleeps until the button is released, dropping its events.
+    pub fn wait_for_release(&mut self) {
+        self.poll();
+        while self.is_pressed() {
+            inputs::wait();
+            self.poll();
+        }
+    }
+}
+
+/// PIR sensor, active while it sees motion.
+pub struct MotionSensor {
+    active: bool,
+}
+
+impl MotionSensor {
+    pub fn new() -> Self {
+        Self { active: false }
+    }
+
+    pub fn is_active(&mut self) -> bool {
+        while let Some(edge) = inputs::next_edge(Source::Motion) {
+            self.active = edge.active;
+        }
+        self.active
+    }
+}
+
+impl Default for MotionSensor {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,35 +1,62 @@
 extern crate alloc;
 
 use alloc::rc::Rc;
-use arrayvec::ArrayString;
+use buzzer::rtttl::Rtttl;
+use buzzer::{play_melody, Buzzer};
 use callback::{Callback, Stopper};
 use core::cell::RefCell;
+use core::convert::Infallible;
+use core::iter;
 use core::ops::DerefMut;
 use cortex_m::delay::Delay;
-use embedded_hal::digital::v2::{InputPin, OutputPin};
-use lcd_1602_i2c::Lcd;
-use rp_pico::hal::gpio::{Output, PushPull};
-use rp_pico::{
-    hal::{
-        gpio::{self, bank0::BankPinId, Function, Input, Pin, PinId, PullUp},
-        I2C,
-    },
-    pac::I2C0,
-};
-
-pub struct CallbackWriteText<DP: PinId + BankPinId, CP: PinId + BankPinId> {
-    text: ArrayString<16>,
-    lcd: Rc<RefCell<Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>>>,
+use display::backlight::Animation;
+use display::{show_message, show_ringing_message, Message, TextDisplay};
+use input::ButtonEvent;
+use rp_pico::hal::i2c;
+use rp_pico::hal::Timer;
+
+use crate::buttons::GestureButton;
+use crate::pwm_buzzer::BuzzerError;
+
+/// Errors raised by the alarm callbacks.
+#[derive(Debug)]
+pub enum CallbackError {
+    /// The LCD did not acknowledge an I²C transfer.
+    Display(i2c::Error),
+    /// The buzzer could not play a note.
+    Buzzer(BuzzerError),
+}
+
+impl From<i2c::Error> for CallbackError {
+    fn from(err: i2c::Error) -> Self {
+        CallbackError::Display(err)
+    }
+}
+
+impl From<BuzzerError> for CallbackError {
+    fn from(err: BuzzerError) -> Self {
+        CallbackError::Buzzer(err)
+    }
+}
+
+// GPIO accesses on the RP2040 cannot fail.
+impl From<Infallible> for CallbackError {
+    fn from(err: Infallible) -> Self {
+        match err {}
+    }
+}
+
+pub struct CallbackWriteText<D: TextDisplay> {
+    text: Message,
+    lcd: Rc<RefCell<D>>,
     delay: Rc<RefCell<Delay>>,
     duration_ms: u32,
 }
 
-impl<DP: PinId + BankPinId, CP: PinId + BankPinId> CallbackWriteText<DP, CP> {
+impl<D: TextDisplay> CallbackWriteText<D> {
     pub fn new(
-        text: ArrayString<16>,
-        lcd: Rc<
-            RefCell<Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>>,
-        >,
+        text: Message,
+        lcd: Rc<RefCell<D>>,
         delay: Rc<RefCell<Delay>>,
         duration_ms: u32,
     ) -> Self {
@@ -42,28 +69,30 @@ impl<DP: PinId + BankPinId, CP: PinId + BankPinId> CallbackWriteText<DP, CP> {
     }
 }
 
-impl<DP: PinId + BankPinId, CP: PinId + BankPinId> CallbackWriteText<DP, CP> {
-    pub fn text(&self) -> ArrayString<16> {
+impl<D: TextDisplay> CallbackWriteText<D> {
+    pub fn text(&self) -> Message {
         self.text
     }
-    pub fn set_text(&mut self, text: ArrayString<16>) {
+    pub fn set_text(&mut self, text: Message) {
         self.text = text;
     }
 }
 
-impl<DP: PinId + BankPinId, CP: PinId + BankPinId> Callback for CallbackWriteText<DP, CP> {
-    fn call(&mut self) -> bool {
-        (*self.lcd)
-            .borrow_mut()
-            .clear((*self.delay).borrow_mut().deref_mut())
-            .unwrap();
-        (*self.lcd).borrow_mut().set_cursor_position(0, 0).unwrap();
-        (*self.lcd)
-            .borrow_mut()
-            .write_str(self.text.as_str())
-            .unwrap();
-        (*self.delay).borrow_mut().delay_ms(self.duration_ms);
-        return true;
+impl<D: TextDisplay> Callback for CallbackWriteText<D>
+where
+    CallbackError: From<D::Error>,
+{
+    type Error = CallbackError;
+
+    fn call(&mut self) -> Result<bool, Self::Error> {
+        (*self.lcd).borrow_mut().clear()?;
+        show_message(
+            (*self.lcd).borrow_mut().deref_mut(),
+            (*self.delay).borrow_mut().deref_mut(),
+            self.text.as_str(),
+            self.duration_ms,
+        )?;
+        return Ok(true);
     }
 }
 
@@ -76,96 +105,101 @@ impl CallbackDoNothing {
 }
 
 impl Callback for CallbackDoNothing {
-    fn call(&mut self) -> bool {
-        return true; // ⸸ CI JIT Guillaume ⸸ (Amen)
+    type Error = Infallible;
+
+    fn call(&mut self) -> Result<bool, Self::Error> {
+        return Ok(true); // ⸸ CI JIT Guillaume ⸸ (Amen)
     }
 }
 
-pub struct CallbackBuzzer<T: PinId, S: Stopper> {
-    buzzer: Rc<RefCell<Pin<T, Output<PushPull>>>>,
-    single_buzz_duration_ms: u32,
+pub struct CallbackBuzzer<B: Buzzer, S: Stopper> {
+    buzzer: Rc<RefCell<B>>,
+    melody: Rtttl<'static>,
     delay: Rc<RefCell<Delay>>,
     stopper: S,
 }
 
-impl<T: PinId, S: Stopper> CallbackBuzzer<T, S> {
+impl<B: Buzzer, S: Stopper> CallbackBuzzer<B, S> {
     pub fn new(
-        buzzer: Rc<RefCell<Pin<T, Output<PushPull>>>>,
-        single_buzz_duration_ms: u32,
+        buzzer: Rc<RefCell<B>>,
+        melody: Rtttl<'static>,
         delay: Rc<RefCell<Delay>>,
         stopper: S,
     ) -> Self {
         Self {
             buzzer,
-            single_buzz_duration_ms,
+            melody,
             delay,
             stopper,
         }
     }
 }
 
-impl<T: PinId, S: Stopper> Callback for CallbackBuzzer<T, S> {
-    fn call(&mut self) -> bool {
-        (*self.buzzer).borrow_mut().set_high().unwrap();
-        (*self.delay)
-            .borrow_mut()
-            .delay_ms(self.single_buzz_duration_ms);
-        (*self.buzzer).borrow_mut().set_low().unwrap();
+impl<B: Buzzer, S: Stopper> Callback for CallbackBuzzer<B, S>
+where
+    CallbackError: From<B::Error> + From<S::Error>,
+{
+    type Error = CallbackError;
+
+    fn call(&mut self) -> Result<bool, Self::Error> {
+        play_melody(
+            (*self.buzzer).borrow_mut().deref_mut(),
+            (*self.delay).borrow_mut().deref_mut(),
+            self.melody.notes(),
+        )?;
         (*self.delay).borrow_mut().delay_ms(500);
-        if self.stopper.should_stop() {
-            return false;
+        if self.stopper.should_stop()? {
+            return Ok(false);
         }
 
-        return true;
+        return Ok(true);
     }
 }
 
-pub struct StopperButton<IP: PinId> {
-    button: Rc<RefCell<Pin<IP, Input<PullUp>>>>,
+/// Stops the alarm when its button is held, so that a short press cannot
+/// acknowledge it by accident.
+pub struct StopperButton {
+    button: Rc<RefCell<GestureButton>>,
 }
 
-impl<IP: PinId> StopperButton<IP> {
-    pub fn new(button: Rc<RefCell<Pin<IP, Input<PullUp>>>>) -> Self {
+impl StopperButton {
+    pub fn new(button: Rc<RefCell<GestureButton>>) -> Self {
         Self { button }
     }
 }
 
-impl<T: PinId> Stopper for StopperButton<T> {
-    fn should_stop(&mut self) -> bool {
-        (*self.button).borrow_mut().is_low().unwrap()
+impl Stopper for StopperButton {
+    type Error = Infallible;
+
+    fn should_stop(&mut self) -> Result<bool, Self::Error> {
+        let events = (*self.button).borrow_mut().poll();
+        Ok(events.contains(&ButtonEvent::LongPress))
     }
 }
 
-pub struct CallbackBuzzerAndWriteText<
-    DP: PinId + BankPinId,
-    CP: PinId + BankPinId,
-    T: PinId,
-    S: Stopper,
-> {
-    text: ArrayString<16>,
-    lcd: Rc<RefCell<Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>>>,
+pub struct CallbackBuzzerAndWriteText<D: TextDisplay, B: Buzzer, S: Stopper> {
+    text: Message,
+    lcd: Rc<RefCell<D>>,
     delay: Rc<RefCell<Delay>>,
     text_duration_ms: u32,
-    buzzer: Rc<RefCell<Pin<T, Output<PushPull>>>>,
-    single_buzz_duration_ms: u32,
+    buzzer: Rc<RefCell<B>>,
+    melody: Rtttl<'static>,
     stopper: S,
-    rgb_color: (u8, u8, u8),
+    animation: Animation,
+    timer: Rc<Timer>,
 }
 
-impl<DP: PinId + BankPinId, CP: PinId + BankPinId, T: PinId, S: Stopper>
-    CallbackBuzzerAndWriteText<DP, CP, T, S>
-{
+impl<D: TextDisplay, B: Buzzer, S: Stopper> CallbackBuzzerAndWriteText<D, B, S> {
     pub fn new(
-        text: ArrayString<16>,
-        lcd: Rc<
-            RefCell<Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>>,
-        >,
+        text: Message,
+        lcd: Rc<RefCell<D>>,
         delay: Rc<RefCell<Delay>>,
         text_duration_ms: u32,
-        buzzer: Rc<RefCell<Pin<T, Output<PushPull>>>>,
-        single_buzz_duration_ms: u32,
+        buzzer: Rc<RefCell<B>>,
+        melody: Rtttl<'static>,
         stopper: S,
-        rgb_color: (u8, u8, u8),
+        animation: Animation,
+        timer: Rc<Timer>,
     ) -> Self {
         Self {
             text,
@@ -173,45 +207,65 @@ impl<DP: PinId + BankPinId, CP: PinId + BankPinId, T: PinId, S: Stopper>
             delay,
             text_duration_ms,
             buzzer,
-            single_buzz_duration_ms,
+            melody,
             stopper,
-            rgb_color,
+            animation,
+            timer,
         }
     }
 }
 
-impl<DP: PinId + BankPinId, CP: PinId + BankPinId, T: PinId, S: Stopper> Callback
-    for CallbackBuzzerAndWriteText<DP, CP, T, S>
+impl<D: TextDisplay, B: Buzzer, S: Stopper> CallbackBuzzerAndWriteText<D, B, S> {
+    pub fn text(&self) -> Message {
+        self.text
+    }
+    pub fn set_text(&mut self, text: Message) {
+        self.text = text;
+    }
+    pub fn animation(&self) -> Animation {
+        self.animation
+    }
+    pub fn set_animation(&mut self, animation: Animation) {
+        self.animation = animation;
+    }
+}
+
+impl<D: TextDisplay, B: Buzzer, S: Stopper> Callback for CallbackBuzzerAndWriteText<D, B, S>
+where
+    CallbackError: From<D::Error> + From<B::Error> + From<S::Error>,
 {
-    fn call(&mut self) -> bool {
-        // Clear the LCD
-        (*self.lcd)
-            .borrow_mut()
-            .clear((*self.delay).borrow_mut().deref_mut())
-            .unwrap();
-        // Set the lcd color
-        (*self.lcd)
-            .borrow_mut()
-            .set_rgb(self.rgb_color.0, self.rgb_color.1, self.rgb_color.2)
-            .unwrap();
-        // Set LCD cursor on first position
-        (*self.lcd).borrow_mut().set_cursor_position(0, 0).unwrap();
-        // Write the text
-        (*self.lcd)
-            .borrow_mut()
-            .write_str(self.text.as_str())
-            .unwrap();
-        // Buzz the buzzer
-        (*self.buzzer).borrow_mut().set_high().unwrap();
-        (*self.delay)
-            .borrow_mut()
-            .delay_ms(self.single_buzz_duration_ms);
-        (*self.buzzer).borrow_mut().set_low().unwrap();
-        if self.stopper.should_stop() {
-            return false;
+    type Error = CallbackError;
+
+    fn call(&mut self) -> Result<bool, Self::Error> {
+        // Write the beginning of the text while the melody plays
+        let mut backlight = show_ringing_message(
+            (*self.lcd).borrow_mut().deref_mut(),
+            self.text.as_str(),
+            self.animation,
+            self.timer.get_counter() / 1000,
+        )?;
+        // Play the alarm melody, animating the backlight between notes
+        for note in self.melody.notes() {
+            play_melody(
+                (*self.buzzer).borrow_mut().deref_mut(),
+                (*self.delay).borrow_mut().deref_mut(),
+                iter::once(note),
+            )?;
+            backlight.update(
+                (*self.lcd).borrow_mut().deref_mut(),
+                self.timer.get_counter() / 1000,
+            )?;
+        }
+        if self.stopper.should_stop()? {
+            return Ok(false);
         }
-        // Wait for the asked duration for the text_display
-        (*self.delay).borrow_mut().delay_ms(self.text_duration_ms);
-        return true;
+        // Show the text for the asked duration, scrolling it if too long
+        show_message(
+            (*self.lcd).borrow_mut().deref_mut(),
+            (*self.delay).borrow_mut().deref_mut(),
+            self.text.as_str(),
+            self.text_duration_ms,
+        )?;
+        return Ok(true);
     }
 }
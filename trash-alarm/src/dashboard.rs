@@ -0,0 +1,36 @@
+use alarm::alarm_manager::AlarmManager;
+use buzzer::Buzzer;
+use callback::Stopper;
+use display::dashboard::AlarmSummary;
+use display::TextDisplay;
+use rp_pico::hal::rtc::DateTime;
+
+use crate::alarm_editor::TrashAlarm;
+use crate::callbacks::CallbackError;
+
+/// What the idle screen shows about the alarms at `now`.
+pub fn alarm_summary<D, B, S>(
+    alarms: &AlarmManager<TrashAlarm<D, B, S>>,
+    now: &DateTime,
+) -> AlarmSummary
+where
+    D: TextDisplay,
+    B: Buzzer,
+    S: Stopper,
+    CallbackError: From<D::Error> + From<B::Error> + From<S::Error>,
+{
+    let description = |index| alarms.alarm(index).unwrap().description();
+    let mut missed = alarms.missed_alarms();
+    AlarmSummary {
+        next: alarms
+            .next_alarm(now)
+            .map(|(index, seconds)| (description(index), seconds)),
+        missed: missed
+            .next()
+            .map(|first| (description(first), 1 + missed.count())),
+        enabled: (0..alarms.len())
+            .filter(|&index| alarms.alarm(index).unwrap().is_enabled())
+            .count(),
+        total: alarms.len(),
+    }
+}
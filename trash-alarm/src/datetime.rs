@@ -1,88 +1,53 @@
 use arrayvec::ArrayString;
-use core::fmt::Write;
-use cortex_m::delay::Delay;
-use embedded_hal::digital::v2::InputPin;
-use lcd_1602_i2c::{Blink, Lcd};
-use rp_pico::{
-    hal::{
-        gpio::{self, bank0::BankPinId, Function, Input, Pin, PinId, PullUp},
-        rtc::{DateTime, DayOfWeek},
-        I2C,
-    },
-    pac::I2C0,
-};
-
-pub trait FormatToArrayString {
-    fn to_date_arraystring(&self) -> ArrayString<10>;
-    fn to_time_arraystring(&self, without_seconds: bool) -> ArrayString<8>;
-    fn to_day_of_week_arraystring(&self) -> ArrayString<8>;
+use clock::drift::{DriftTracker, SyncSource};
+use clock::entry::{EntryEvent, EntryView, Inactivity, Phase, TimeEntry, ENTRY_TIMEOUT_MS};
+use core::fmt::{Debug, Write};
+use display::TextDisplay;
+use embedded_hal::blocking::i2c;
+use input::ButtonEvent;
+use locale::{Text, LANGUAGE};
+use rp_pico::hal::rtc::{DateTime, DayOfWeek, RealTimeClock};
+use time_source::ExternalClock;
+
+use crate::buttons::GestureButton;
+use crate::globals::ExternalRtc;
+use crate::inputs;
+use crate::storage::{self, Settings};
+
+pub trait FromScreenAndButtons: Sized {
+    /// Asks the user for a value, starting from `initial`.
+    ///
+    /// Without any input for a while, `None` is returned so that an
+    /// unattended reboot still ends up with a running clock, from `initial`.
+    fn from_screen_and_buttons<D: TextDisplay>(
+        initial: Self,
+        lcd: &mut D,
+        increment_button: &mut GestureButton,
+        validate_button: &mut GestureButton,
+    ) -> Option<Self>
+    where
+        D::Error: Debug;
 }
 
-pub trait FromScreenAndButtons {
-    fn from_screen_and_buttons<DP: PinId + BankPinId, CP: PinId + BankPinId, IP: PinId, VP: PinId>(
-        lcd: &mut Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>,
-        delay: &mut Delay,
-        increment_button: &mut Pin<IP, Input<PullUp>>,
-        validate_button: &mut Pin<VP, Input<PullUp>>,
-    ) -> Self;
-}
-
-fn get_day_of_week_string(day_of_week: DayOfWeek) -> ArrayString<8> {
-    let ret = match day_of_week {
-        DayOfWeek::Monday => "Lundi",
-        DayOfWeek::Tuesday => "Mardi",
-        DayOfWeek::Wednesday => "Mercredi",
-        DayOfWeek::Thursday => "Jeudi",
-        DayOfWeek::Friday => "Vendredi",
-        DayOfWeek::Saturday => "Samedi",
-        DayOfWeek::Sunday => "Dimanche",
-    };
-
-    let mut ret_arrstr = ArrayString::<8>::new();
-    write!(ret_arrstr, "{: <8}", ret).unwrap();
-    // write!(ret_arrstr, "{}", ret).unwrap();
-    return ret_arrstr;
-}
-
-#[derive(PartialEq)]
-enum ButtonPhase {
-    ButtonPhaseDayOfWeek = 0,
-    ButtonPhaseTimeHourTens = 1,
-    ButtonPhaseTimeHourUnits = 2,
-    ButtonPhaseTimeMinuteTens = 3,
-    ButtonPhaseTimeMinuteUnits = 4,
-    ButtonPhaseFinished = 5,
-}
-
-fn get_button_phase_from_u8(button_phase: &u8) -> ButtonPhase {
-    return match button_phase {
-        0 => ButtonPhase::ButtonPhaseDayOfWeek,
-        1 => ButtonPhase::ButtonPhaseTimeHourTens,
-        2 => ButtonPhase::ButtonPhaseTimeHourUnits,
-        3 => ButtonPhase::ButtonPhaseTimeMinuteTens,
-        4 => ButtonPhase::ButtonPhaseTimeMinuteUnits,
-        5 => ButtonPhase::ButtonPhaseFinished,
-        _ => ButtonPhase::ButtonPhaseFinished,
-    };
-}
-
-fn get_button_phase_string(button_phase: &ButtonPhase) -> ArrayString<16> {
-    let ret = match button_phase {
-        ButtonPhase::ButtonPhaseDayOfWeek => "Jour Semaine ?",
-        ButtonPhase::ButtonPhaseTimeHourTens => "Dizaine Heure ?",
-        ButtonPhase::ButtonPhaseTimeHourUnits => "Unite Heure ?",
-        ButtonPhase::ButtonPhaseTimeMinuteTens => "Dizaine Minute ?",
-        ButtonPhase::ButtonPhaseTimeMinuteUnits => "Unite Minute ?",
-        ButtonPhase::ButtonPhaseFinished => "Finished ?",
-    };
-
-    let mut ret_arrstr = ArrayString::<16>::new();
+// Accented letters take more than one byte
+fn get_phase_string(phase: Phase) -> ArrayString<64> {
+    let ret = LANGUAGE.text(match phase {
+        Phase::Year => Text::EnterYear,
+        Phase::Month => Text::EnterMonth,
+        Phase::Day => Text::EnterDay,
+        Phase::HourTens => Text::EnterHourTens,
+        Phase::HourUnits => Text::EnterHourUnits,
+        Phase::MinuteTens => Text::EnterMinuteTens,
+        Phase::MinuteUnits => Text::EnterMinuteUnits,
+        Phase::Finished => Text::EntryFinished,
+    });
+
+    let mut ret_arrstr = ArrayString::<64>::new();
     write!(ret_arrstr, "{: <16}", ret).unwrap();
-    // write!(ret_arrstr, "{}", ret).unwrap();
     return ret_arrstr;
 }
 
-fn day_of_week_from_u8(v: u8) -> DayOfWeek {
+pub fn day_of_week_from_u8(v: u8) -> DayOfWeek {
     match v {
         0 => DayOfWeek::Sunday,
         1 => DayOfWeek::Monday,
@@ -96,147 +61,129 @@ fn day_of_week_from_u8(v: u8) -> DayOfWeek {
 }
 
 impl FromScreenAndButtons for DateTime {
-    fn from_screen_and_buttons<
-        DP: PinId + BankPinId,
-        CP: PinId + BankPinId,
-        IP: PinId,
-        VP: PinId,
-    >(
-        lcd: &mut Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>,
-        delay: &mut Delay,
-        increment_button: &mut Pin<IP, Input<PullUp>>,
-        validate_button: &mut Pin<VP, Input<PullUp>>,
-    ) -> Self {
-        // Année
-        let mut datetime = DateTime {
-            year: 0,
-            day: 1,
-            month: 1,
-            day_of_week: DayOfWeek::Monday,
-            hour: 0,
-            minute: 0,
-            second: 0,
-        };
-
-        let mut button_phase = ButtonPhase::ButtonPhaseDayOfWeek;
+    fn from_screen_and_buttons<D: TextDisplay>(
+        initial: Self,
+        lcd: &mut D,
+        increment_button: &mut GestureButton,
+        validate_button: &mut GestureButton,
+    ) -> Option<Self>
+    where
+        D::Error: Debug,
+    {
+        let mut entry = TimeEntry::new(from_rtc_datetime(&initial));
+        let mut inactivity = Inactivity::new(ENTRY_TIMEOUT_MS, inputs::now_ms());
+        render(&entry.view(), lcd);
 
         loop {
-            if increment_button.is_low().unwrap() {
-                match button_phase {
-                    ButtonPhase::ButtonPhaseDayOfWeek => {
-                        datetime.day_of_week =
-                            day_of_week_from_u8((datetime.day_of_week as u8 + 1) % 7)
+            let mut changed = false;
+            // A click increments, holding the button decrements repeatedly
+            for event in increment_button.poll() {
+                match event {
+                    ButtonEvent::ShortPress => entry.handle(EntryEvent::Increment),
+                    ButtonEvent::LongPress | ButtonEvent::Repeat => {
+                        entry.handle(EntryEvent::Decrement)
                     }
-                    ButtonPhase::ButtonPhaseTimeHourTens => {
-                        datetime.hour = (datetime.hour + 10) % 30
-                    }
-                    ButtonPhase::ButtonPhaseTimeHourUnits => {
-                        datetime.hour = ((datetime.hour + 1)
-                            % (if datetime.hour / 10 == 2 { 4 } else { 10 }))
-                            + datetime.hour / 10 * 10
-                    }
-                    ButtonPhase::ButtonPhaseTimeMinuteTens => {
-                        datetime.minute = (datetime.minute + 10) % 60
-                    }
-                    ButtonPhase::ButtonPhaseTimeMinuteUnits => {
-                        datetime.minute = (datetime.minute + 1) % 10 + datetime.minute / 10 * 10
-                    }
-
                     _ => {}
                 }
-                while increment_button.is_low().unwrap() {}
-                delay.delay_ms(500); // We wait to avoid multiple triggers
+                inactivity.touch(inputs::now_ms());
+                changed = true;
             }
 
-            if validate_button.is_low().unwrap() {
-                let incr_button_phase = button_phase as u8 + 1;
-                button_phase = get_button_phase_from_u8(&incr_button_phase);
-                delay.delay_ms(500); // We wait to avoid multiple triggers
-                while validate_button.is_low().unwrap() {}
+            for event in validate_button.poll() {
+                if let ButtonEvent::ShortPress | ButtonEvent::DoublePress = event {
+                    entry.handle(EntryEvent::Validate);
+                }
+                inactivity.touch(inputs::now_ms());
+                changed = true;
+            }
+
+            if inactivity.is_expired(inputs::now_ms()) {
+                entry.handle(EntryEvent::Timeout);
             }
 
-            if button_phase == ButtonPhase::ButtonPhaseFinished {
-                lcd.set_blink(Blink::Off).unwrap();
-                lcd.clear(delay).unwrap();
-                return datetime;
+            if entry.is_finished() {
+                lcd.set_blink(false).unwrap();
+                lcd.clear().unwrap();
+                return (!entry.timed_out()).then(|| to_rtc_datetime(&entry.datetime()));
+            }
+            if changed {
+                render(&entry.view(), lcd);
             }
-            render(&datetime, lcd, delay, &button_phase);
-            // We wait for the next user input.
-            while !increment_button.is_low().unwrap() && !validate_button.is_low().unwrap() {}
+            // We sleep until the next user input or the timeout.
+            inputs::wake_at(inactivity.deadline_ms());
+            inputs::wait();
         }
     }
 }
 
-fn render<DP: PinId + BankPinId, CP: PinId + BankPinId>(
-    datetime: &DateTime,
-    lcd: &mut Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>,
-    delay: &mut Delay,
-    button_phase: &ButtonPhase,
-) {
-    let str_lcd_phase = get_button_phase_string(button_phase);
-    let str_lcd_value = match button_phase {
-        ButtonPhase::ButtonPhaseDayOfWeek => get_day_of_week_string(datetime.day_of_week),
-        ButtonPhase::ButtonPhaseTimeHourTens => datetime.to_time_arraystring(true),
-        ButtonPhase::ButtonPhaseTimeHourUnits => datetime.to_time_arraystring(true),
-        ButtonPhase::ButtonPhaseTimeMinuteTens => datetime.to_time_arraystring(true),
-        ButtonPhase::ButtonPhaseTimeMinuteUnits => datetime.to_time_arraystring(true),
-        ButtonPhase::ButtonPhaseFinished => ArrayString::<8>::new(),
-    };
-
-    lcd.clear(delay).unwrap();
-
-    lcd.set_cursor_position(0, 0).unwrap();
-    lcd.write_str(str_lcd_phase.as_str()).unwrap();
-
-    lcd.set_cursor_position(0, 1).unwrap();
-    lcd.write_str(str_lcd_value.as_str()).unwrap();
-
-    match button_phase {
-        ButtonPhase::ButtonPhaseTimeHourUnits => blink_digit(lcd, 1),
-        ButtonPhase::ButtonPhaseTimeHourTens => blink_digit(lcd, 0),
-        ButtonPhase::ButtonPhaseTimeMinuteTens => blink_digit(lcd, 3),
-        ButtonPhase::ButtonPhaseTimeMinuteUnits => blink_digit(lcd, 4),
-        ButtonPhase::ButtonPhaseFinished => {}
-        ButtonPhase::ButtonPhaseDayOfWeek => {}
+pub fn to_rtc_datetime(datetime: &clock::DateTime) -> DateTime {
+    DateTime {
+        year: datetime.year,
+        month: datetime.month,
+        day: datetime.day,
+        day_of_week: day_of_week_from_u8(datetime.day_of_week()),
+        hour: datetime.hour,
+        minute: datetime.minute,
+        second: datetime.second,
     }
 }
 
-fn blink_digit<DP: PinId + BankPinId, CP: PinId + BankPinId>(
-    lcd: &mut Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>,
-    pos_digit: u8,
+/// Sets the internal and the external clocks to a trusted UTC time, e.g. a
+/// received one, recording the drift of the internal clock.
+pub fn sync_clocks<I: i2c::Write>(
+    real_time_clock: &mut RealTimeClock,
+    external_rtc: &mut ExternalRtc,
+    i2c: &mut I,
+    drift: &mut DriftTracker,
+    source: SyncSource,
+    datetime: &clock::DateTime,
 ) {
-    lcd.set_cursor_position(pos_digit, 1).unwrap();
-    lcd.set_blink(Blink::On).unwrap();
+    let time = datetime.timestamp();
+    let clock = real_time_clock
+        .now()
+        .ok()
+        .map(|now| from_rtc_datetime(&now).timestamp());
+    if drift.record(source, time, clock) {
+        storage::save(&Settings {
+            drift_ppb: drift.ppb(),
+        });
+    }
+    // Setting the clocks rounds them to the second, right ones are left alone
+    if clock != Some(time) {
+        real_time_clock.set_datetime(to_rtc_datetime(datetime)).ok();
+        external_rtc.set_time(i2c, datetime).ok();
+    }
 }
 
-impl FormatToArrayString for DateTime {
-    fn to_date_arraystring(&self) -> ArrayString<10> {
-        let mut date_string = ArrayString::<10>::new();
-        write!(
-            &mut date_string,
-            "{:0>4}/{:0>2}/{:0>2}",
-            self.year, self.month, self.day
-        )
-        .unwrap();
-        return date_string;
+pub fn from_rtc_datetime(datetime: &DateTime) -> clock::DateTime {
+    clock::DateTime {
+        year: datetime.year,
+        month: datetime.month,
+        day: datetime.day,
+        hour: datetime.hour,
+        minute: datetime.minute,
+        second: datetime.second,
     }
+}
 
-    fn to_time_arraystring(&self, without_seconds: bool) -> ArrayString<8> {
-        let mut time_string = ArrayString::<8>::new();
-        if without_seconds {
-            write!(&mut time_string, "{:0>2}:{:0>2}", self.hour, self.minute).unwrap();
-        } else {
-            write!(
-                &mut time_string,
-                "{:0>2}:{:0>2}:{:0>2}",
-                self.hour, self.minute, self.second
-            )
-            .unwrap();
-        }
-        return time_string;
-    }
+fn render<D: TextDisplay>(view: &EntryView, lcd: &mut D)
+where
+    D::Error: Debug,
+{
+    lcd.clear().unwrap();
+
+    lcd.set_cursor_position(0, 0).unwrap();
+    lcd.write_str(get_phase_string(view.phase).as_str())
+        .unwrap();
+
+    lcd.set_cursor_position(0, 1).unwrap();
+    lcd.write_str(view.value.as_str()).unwrap();
 
-    fn to_day_of_week_arraystring(&self) -> ArrayString<8> {
-        return get_day_of_week_string(self.day_of_week);
+    match view.cursor {
+        Some(column) => {
+            lcd.set_cursor_position(column, 1).unwrap();
+            lcd.set_blink(true).unwrap();
+        }
+        None => lcd.set_blink(false).unwrap(),
     }
 }
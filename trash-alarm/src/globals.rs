@@ -1,4 +1,5 @@
 use alloc_cortex_m::CortexMHeap;
+use clock::TimeZone;
 use core::alloc::Layout;
 use rp_pico::hal::multicore::Stack;
 
@@ -14,6 +15,17 @@ pub fn oom(_: Layout) -> ! {
 pub const LCD_ADDRESS: u8 = 0x7c >> 1;
 pub const RGB_ADDRESS: u8 = 0xc0 >> 1;
 
+/// Battery-backed clock sharing the I2C bus of the LCD
+#[cfg(not(feature = "pcf8563"))]
+pub type ExternalRtc = time_source::Ds3231;
+#[cfg(feature = "pcf8563")]
+pub type ExternalRtc = time_source::Pcf8563;
+
+/// The clocks keep the UTC time, shown and used by the alarms in this zone
+pub const TIME_ZONE: TimeZone = clock::timezone::EU_CENTRAL;
+/// The GPS sends the time every second, the clocks are only set this often
+pub const GPS_SYNC_PERIOD_MS: u64 = 3600 * 1000;
+
 /// Stack for core 1
 ///
 /// Core 0 gets its stack via the normal route - any memory not used by static
@@ -0,0 +1,81 @@
+//! GPS receiver read through the UART 1 receive interrupt.
+//!
+//! The receiver sends a burst of sentences every second, faster than the
+//! UART FIFO fills up while the core sleeps in `inputs::wait`. The interrupt
+//! handler decodes them as they arrive and keeps the last time received,
+//! which also wakes the core up.
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use rp_pico::hal::gpio::bank0::Gpio21;
+use rp_pico::hal::gpio::{Function, Pin, Uart};
+use rp_pico::hal::pac::{self, interrupt, UART1};
+use rp_pico::hal::uart::{self, Enabled, UartPeripheral};
+use time_source::NmeaReader;
+
+use crate::inputs;
+
+pub type GpsUart =
+    UartPeripheral<Enabled, UART1, uart::Pins<(), Pin<Gpio21, Function<Uart>>, (), ()>>;
+
+/// UTC time sent by the receiver, at `at_ms` since boot.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct GpsTime {
+    pub datetime: clock::DateTime,
+    pub at_ms: u64,
+}
+
+struct Receiver {
+    uart: GpsUart,
+    reader: NmeaReader,
+}
+
+static RECEIVER: Mutex<RefCell<Option<Receiver>>> = Mutex::new(RefCell::new(None));
+static LAST_TIME: Mutex<RefCell<Option<GpsTime>>> = Mutex::new(RefCell::new(None));
+
+/// Moves the UART under the control of the interrupt handler.
+pub fn init(mut uart: GpsUart) {
+    uart.enable_rx_interrupt();
+    critical_section::with(|cs| {
+        RECEIVER.borrow_ref_mut(cs).replace(Receiver {
+            uart,
+            reader: NmeaReader::new(),
+        });
+    });
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::UART1_IRQ);
+    }
+}
+
+/// Last time received and not read yet.
+pub fn take_time() -> Option<GpsTime> {
+    critical_section::with(|cs| LAST_TIME.borrow_ref_mut(cs).take())
+}
+
+#[interrupt]
+fn UART1_IRQ() {
+    let at_ms = inputs::now_ms();
+    critical_section::with(|cs| {
+        let mut receiver = RECEIVER.borrow_ref_mut(cs);
+        let receiver = match receiver.as_mut() {
+            Some(receiver) => receiver,
+            None => return,
+        };
+        let mut bytes = [0; 32];
+        // Reading empties the FIFO, which clears the interrupt. Errors, e.g.
+        // an overrun, only spoil the current sentence.
+        while receiver.uart.uart_is_readable() {
+            let count = match receiver.uart.read_raw(&mut bytes) {
+                Ok(count) => count,
+                Err(_) => continue,
+            };
+            for &byte in &bytes[..count] {
+                if let Some(Ok(datetime)) = receiver.reader.push(byte) {
+                    LAST_TIME
+                        .borrow_ref_mut(cs)
+                        .replace(GpsTime { datetime, at_ms });
+                }
+            }
+        }
+    });
+}
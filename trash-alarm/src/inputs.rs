@@ -0,0 +1,213 @@
+//! Buttons, motion sensor and radio time receiver read through GPIO edge
+//! interrupts.
+//!
+//! The interrupt handler timestamps every edge into the queue of its input,
+//! the application pops them when it has time so that short presses are not
+//! missed while it is busy. While a button is held, and for a moment after
+//! the last edge, the timer alarm 0 wakes the core up periodically so that
+//! long presses and pending clicks are recognised without any edge. It also
+//! wakes the core up at the time given to `wake_at`, e.g. for a timeout.
+
+use core::cell::RefCell;
+use critical_section::Mutex;
+use embedded_hal::digital::v2::InputPin;
+use fugit::MicrosDurationU32;
+use input::{Edge, EventQueue};
+use rp_pico::hal::gpio::bank0::{Gpio1, Gpio16, Gpio17, Gpio5};
+use rp_pico::hal::gpio::{Input, Interrupt, Pin, PullUp};
+use rp_pico::hal::pac::{self, interrupt};
+use rp_pico::hal::timer::{Alarm, Alarm0};
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Source {
+    Increment = 0,
+    Validate = 1,
+    Motion = 2,
+    Radio = 3,
+}
+
+const SOURCES: usize = 4;
+const QUEUE_CAPACITY: usize = 16;
+const TICK_MS: u32 = 10;
+// Gestures still need time after the last edge, e.g. to end a double press
+// window.
+const TICK_AFTER_EDGE_MS: u64 = 1000;
+// The alarm counts 32 bits microseconds
+const LONGEST_SLEEP_MS: u64 = 1_000_000;
+
+struct Pins {
+    increment: Pin<Gpio5, Input<PullUp>>,
+    validate: Pin<Gpio1, Input<PullUp>>,
+    motion: Pin<Gpio16, Input<PullUp>>,
+    radio: Pin<Gpio17, Input<PullUp>>,
+    tick: Alarm0,
+    last_edge_ms: u64,
+    wake_at_ms: Option<u64>,
+}
+
+impl Pins {
+    fn any_button_pressed(&self) -> bool {
+        self.increment.is_low().unwrap() || self.validate.is_low().unwrap()
+    }
+
+    // Schedules the next wake up, if any is needed.
+    fn schedule_wake_up(&mut self, now_ms: u64) {
+        let sleep_ms = if self.any_button_pressed()
+            || now_ms.saturating_sub(self.last_edge_ms) < TICK_AFTER_EDGE_MS
+        {
+            Some(TICK_MS as u64)
+        } else {
+            self.wake_at_ms
+                .map(|at_ms| at_ms.saturating_sub(now_ms).clamp(1, LONGEST_SLEEP_MS))
+        };
+        if let Some(sleep_ms) = sleep_ms {
+            self.tick
+                .schedule(MicrosDurationU32::millis(sleep_ms as u32))
+                .ok();
+        }
+    }
+}
+
+static PINS: Mutex<RefCell<Option<Pins>>> = Mutex::new(RefCell::new(None));
+static EDGES: Mutex<RefCell<[EventQueue<Edge, QUEUE_CAPACITY>; SOURCES]>> =
+    Mutex::new(RefCell::new([
+        EventQueue::new(),
+        EventQueue::new(),
+        EventQueue::new(),
+        EventQueue::new(),
+    ]));
+
+/// Moves the inputs under the control of the interrupt handlers.
+pub fn init(
+    increment: Pin<Gpio5, Input<PullUp>>,
+    validate: Pin<Gpio1, Input<PullUp>>,
+    motion: Pin<Gpio16, Input<PullUp>>,
+    radio: Pin<Gpio17, Input<PullUp>>,
+    mut tick: Alarm0,
+) {
+    for interrupt in [Interrupt::EdgeLow, Interrupt::EdgeHigh] {
+        increment.set_interrupt_enabled(interrupt, true);
+        validate.set_interrupt_enabled(interrupt, true);
+        motion.set_interrupt_enabled(interrupt, true);
+        radio.set_interrupt_enabled(interrupt, true);
+    }
+    tick.enable_interrupt();
+    critical_section::with(|cs| {
+        PINS.borrow_ref_mut(cs).replace(Pins {
+            increment,
+            validate,
+            motion,
+            radio,
+            tick,
+            last_edge_ms: 0,
+            wake_at_ms: None,
+        });
+    });
+    unsafe {
+        pac::NVIC::unmask(pac::Interrupt::IO_IRQ_BANK0);
+        pac::NVIC::unmask(pac::Interrupt::TIMER_IRQ_0);
+    }
+}
+
+/// Oldest edge of `source` not read yet.
+pub fn next_edge(source: Source) -> Option<Edge> {
+    critical_section::with(|cs| EDGES.borrow_ref_mut(cs)[source as usize].pop())
+}
+
+/// Sleeps until an interrupt, unless user input is already waiting.
+pub fn wait() {
+    // Interrupts masked, a pending one still ends the `wfi`.
+    cortex_m::interrupt::free(|_| {
+        // The radio is read by the main loop only, its edges wake the core
+        // up but do not need to be read.
+        let empty = critical_section::with(|cs| {
+            EDGES.borrow_ref(cs)[..Source::Radio as usize]
+                .iter()
+                .all(EventQueue::is_empty)
+        });
+        if empty {
+            cortex_m::asm::wfi();
+        }
+    });
+}
+
+/// Makes sure that `wait` returns at `at_ms` at the latest.
+pub fn wake_at(at_ms: u64) {
+    let now_ms = now_ms();
+    critical_section::with(|cs| {
+        if let Some(pins) = PINS.borrow_ref_mut(cs).as_mut() {
+            pins.wake_at_ms = Some(pins.wake_at_ms.map_or(at_ms, |ms| ms.min(at_ms)));
+            pins.schedule_wake_up(now_ms);
+        }
+    });
+}
+
+/// Milliseconds since boot, readable from interrupts unlike the shared
+/// `Timer`.
+pub fn now_ms() -> u64 {
+    // Same reading as `Timer::get_counter`
+    let timer = unsafe { &*pac::TIMER::ptr() };
+    let mut high = timer.timerawh.read().bits();
+    loop {
+        let low = timer.timerawl.read().bits();
+        let high_after = timer.timerawh.read().bits();
+        if high == high_after {
+            break ((high as u64) << 32 | low as u64) / 1000;
+        }
+        high = high_after;
+    }
+}
+
+#[interrupt]
+fn IO_IRQ_BANK0() {
+    let at_ms = now_ms();
+    critical_section::with(|cs| {
+        let mut pins = PINS.borrow_ref_mut(cs);
+        let pins = match pins.as_mut() {
+            Some(pins) => pins,
+            None => return,
+        };
+        let mut edges = EDGES.borrow_ref_mut(cs);
+        // Bounces can raise both edges, the current level is what matters.
+        macro_rules! collect {
+            ($pin:expr, $source:expr, $active:ident) => {
+                let (low, high) = (
+                    $pin.interrupt_status(Interrupt::EdgeLow),
+                    $pin.interrupt_status(Interrupt::EdgeHigh),
+                );
+                if low || high {
+                    $pin.clear_interrupt(Interrupt::EdgeLow);
+                    $pin.clear_interrupt(Interrupt::EdgeHigh);
+                    edges[$source as usize].push(Edge {
+                        active: $pin.$active().unwrap(),
+                        at_ms,
+                    });
+                    if $source == Source::Increment || $source == Source::Validate {
+                        pins.last_edge_ms = at_ms;
+                        pins.schedule_wake_up(at_ms);
+                    }
+                }
+            };
+        }
+        collect!(pins.increment, Source::Increment, is_low);
+        collect!(pins.validate, Source::Validate, is_low);
+        collect!(pins.motion, Source::Motion, is_high);
+        // High during the pulses, modules with an inverted output need `is_low`
+        collect!(pins.radio, Source::Radio, is_high);
+    });
+}
+
+#[interrupt]
+fn TIMER_IRQ_0() {
+    let now_ms = now_ms();
+    critical_section::with(|cs| {
+        if let Some(pins) = PINS.borrow_ref_mut(cs).as_mut() {
+            pins.tick.clear_interrupt();
+            if pins.wake_at_ms.map_or(false, |at_ms| at_ms <= now_ms) {
+                pins.wake_at_ms = None;
+            }
+            // Waking up is all the tick is for.
+            pins.schedule_wake_up(now_ms);
+        }
+    });
+}
@@ -1,57 +1,159 @@
-use rp_pico::hal::gpio::{bank0::BankPinId, Function, Pin, PinId};
-use rp_pico::hal::rtc::DateTime;
-use rp_pico::hal::{gpio, Timer, I2C};
-use rp_pico::pac::I2C0;
+use display::{charset, TextDisplay};
+use embedded_hal::blocking::delay::DelayMs;
+use embedded_hal::blocking::i2c;
 
-use crate::datetime::FormatToArrayString;
+// Control bytes of the AiP31068 I²C interface
+const COMMAND: u8 = 0x80;
+const DATA: u8 = 0x40;
 
-pub trait WriteCurrentDayAndTime {
-    fn write_current_day_and_time(&mut self, time: &DateTime);
-}
+// HD44780 instructions
+const CLEAR_DISPLAY: u8 = 0x01;
+const ENTRY_MODE_SET: u8 = 0x04;
+const ENTRY_LEFT: u8 = 0x02;
+const DISPLAY_CONTROL: u8 = 0x08;
+const DISPLAY_ON: u8 = 0x04;
+const BLINK_ON: u8 = 0x01;
+// 4 bit bus, 2 lines, 5x8 dots
+const FUNCTION_SET: u8 = 0x28;
+const SET_CGRAM_ADDRESS: u8 = 0x40;
+const SET_DDRAM_ADDRESS: u8 = 0x80;
+const SECOND_ROW_ADDRESS: u8 = 0x40;
+
+// PCA9633 backlight registers
+const REG_MODE1: u8 = 0x00;
+const REG_MODE2: u8 = 0x01;
+const REG_BLUE: u8 = 0x02;
+const REG_GREEN: u8 = 0x03;
+const REG_RED: u8 = 0x04;
+const REG_OUTPUT: u8 = 0x08;
 
-pub trait RainbowAnimation {
-    fn animate_rainbow(&mut self, loop_duration_ms: u64, timer: &mut Timer);
+/// Waveshare 1602 RGB LCD on an I²C bus: an AiP31068 character controller
+/// and a PCA9633 backlight driver.
+pub struct Lcd1602<I: i2c::Write> {
+    i2c: I,
+    address: u8,
+    rgb_address: u8,
+    display_control: u8,
+    clear_delay: BusyDelay,
 }
 
-impl<DP: PinId + BankPinId, CP: PinId + BankPinId> WriteCurrentDayAndTime
-    for lcd_1602_i2c::Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>
-{
-    fn write_current_day_and_time(&mut self, time: &DateTime) {
-        self.set_cursor_position(0, 0).unwrap();
-        self.write_str(time.to_day_of_week_arraystring().as_str())
-            .unwrap();
-        self.set_cursor_position(0, 1).unwrap();
-        self.write_str(time.to_time_arraystring(false).as_str())
-            .unwrap();
+impl<I: i2c::Write> Lcd1602<I> {
+    pub fn new<DL: DelayMs<u16>>(
+        i2c: I,
+        address: u8,
+        rgb_address: u8,
+        delay: &mut DL,
+        system_clock_hz: u32,
+    ) -> Result<Self, I::Error> {
+        let mut lcd = Self {
+            i2c,
+            address,
+            rgb_address,
+            display_control: DISPLAY_CONTROL | DISPLAY_ON,
+            clear_delay: BusyDelay::new(system_clock_hz),
+        };
+        lcd.init(delay)?;
+        Ok(lcd)
+    }
+
+    /// The I²C bus, shared with the other devices on it.
+    pub fn i2c_mut(&mut self) -> &mut I {
+        &mut self.i2c
+    }
+
+    // Power-up sequence of the HD44780 datasheet
+    fn init<DL: DelayMs<u16>>(&mut self, delay: &mut DL) -> Result<(), I::Error> {
+        // At least 40ms are needed before sending commands
+        delay.delay_ms(80);
+        self.write_command(FUNCTION_SET)?;
+        delay.delay_ms(5);
+        self.write_command(FUNCTION_SET)?;
+        delay.delay_ms(5);
+        self.write_command(FUNCTION_SET)?;
+        self.write_command(self.display_control)?;
+        self.clear()?;
+        self.write_command(ENTRY_MODE_SET | ENTRY_LEFT)?;
+
+        self.write_register(REG_MODE1, 0)?;
+        // LEDs controllable by both PWM and GRPPWM registers
+        self.write_register(REG_OUTPUT, 0xFF)?;
+        self.write_register(REG_MODE2, 0x20)
+    }
+
+    fn write_command(&mut self, command: u8) -> Result<(), I::Error> {
+        self.i2c.write(self.address, &[COMMAND, command])
+    }
+
+    fn write_data(&mut self, data: u8) -> Result<(), I::Error> {
+        self.i2c.write(self.address, &[DATA, data])
+    }
+
+    fn write_register(&mut self, register: u8, value: u8) -> Result<(), I::Error> {
+        self.i2c.write(self.rgb_address, &[register, value])
     }
 }
 
-impl<DP: PinId + BankPinId, CP: PinId + BankPinId> RainbowAnimation
-    for lcd_1602_i2c::Lcd<I2C<I2C0, (Pin<DP, Function<gpio::I2C>>, Pin<CP, Function<gpio::I2C>>)>>
-{
-    fn animate_rainbow(&mut self, loop_duration_ms: u64, timer: &mut Timer) {
-        let (mut r, mut g, mut b) = (0u8, 0u8, 0u8);
-        let current_time_ms = timer.get_counter() / 1000;
-        let loop_progress = (current_time_ms % loop_duration_ms) as f32 / (loop_duration_ms as f32);
-        if loop_progress < 1. / 6. {
-            r = 255;
-            g = (255. * (loop_progress * 6. - 0.)) as u8;
-        } else if loop_progress < 2. / 6. {
-            g = 255;
-            r = (255. - (255. * (loop_progress * 6. - 1.))) as u8;
-        } else if loop_progress < 3. / 6. {
-            g = 255;
-            b = (255. * (loop_progress * 6. - 2.)) as u8;
-        } else if loop_progress < 4. / 6. {
-            b = 255;
-            g = (255. - (255. * (loop_progress * 6. - 3.))) as u8;
-        } else if loop_progress < 5. / 6. {
-            b = 255;
-            r = (255. * (loop_progress * 6. - 4.)) as u8;
+impl<I: i2c::Write> TextDisplay for Lcd1602<I> {
+    type Error = I::Error;
+
+    fn clear(&mut self) -> Result<(), Self::Error> {
+        let result = self.write_command(CLEAR_DISPLAY);
+        self.clear_delay.delay_ms(2);
+        result
+    }
+
+    fn set_cursor_position(&mut self, column: u8, row: u8) -> Result<(), Self::Error> {
+        let row_address = if row == 0 { 0 } else { SECOND_ROW_ADDRESS };
+        self.write_command(SET_DDRAM_ADDRESS | (row_address + column))
+    }
+
+    fn set_blink(&mut self, blink: bool) -> Result<(), Self::Error> {
+        if blink {
+            self.display_control |= BLINK_ON;
         } else {
-            r = 255;
-            b = (255. - (255. * (loop_progress * 6. - 5.))) as u8;
+            self.display_control &= !BLINK_ON;
         }
-        self.set_rgb(r, g, b).unwrap();
+        self.write_command(self.display_control)
+    }
+
+    fn write_str(&mut self, text: &str) -> Result<(), Self::Error> {
+        for code in charset::encode(text) {
+            self.write_data(code)?;
+        }
+        Ok(())
+    }
+
+    fn set_rgb(&mut self, r: u8, g: u8, b: u8) -> Result<(), Self::Error> {
+        self.write_register(REG_RED, r)?;
+        self.write_register(REG_GREEN, g)?;
+        self.write_register(REG_BLUE, b)
+    }
+
+    fn upload_glyph(&mut self, slot: u8, bitmap: &[u8; 8]) -> Result<(), Self::Error> {
+        self.write_command(SET_CGRAM_ADDRESS | ((slot & 0x07) << 3))?;
+        for row in bitmap {
+            self.write_data(row & 0x1F)?;
+        }
+        Ok(())
+    }
+}
+
+// The LCD needs a short pause after a clear. Busy-waiting keeps the display
+// usable while the shared `Delay` is borrowed elsewhere.
+struct BusyDelay {
+    cycles_per_ms: u32,
+}
+
+impl BusyDelay {
+    fn new(system_clock_hz: u32) -> Self {
+        Self {
+            cycles_per_ms: system_clock_hz / 1000,
+        }
+    }
+}
+
+impl DelayMs<u16> for BusyDelay {
+    fn delay_ms(&mut self, ms: u16) {
+        cortex_m::asm::delay(self.cycles_per_ms * ms as u32);
     }
 }
@@ -2,12 +2,19 @@
 #![no_main]
 #![feature(alloc_error_handler)]
 
+pub mod alarm_editor;
+pub mod buttons;
 pub mod callbacks;
 pub mod core_tasks;
+pub mod dashboard;
 pub mod datetime;
 pub mod globals;
+pub mod gps;
+pub mod inputs;
 pub mod lcd;
 pub mod led;
+pub mod pwm_buzzer;
+pub mod storage;
 
 extern crate alloc;
 
@@ -15,14 +22,27 @@ use alarm::alarm_manager::AlarmManager;
 use alloc::rc::Rc;
 use alloc::vec;
 use arrayvec::ArrayString;
+use buttons::{GestureButton, MotionSensor};
 use callbacks::CallbackBuzzerAndWriteText;
+use clock::drift::{DriftTracker, SyncSource};
+use clock::entry::FIRST_YEAR;
 use core::cell::RefCell;
 use core::ops::DerefMut;
 use core::u8;
+use dashboard::alarm_summary;
 use datetime::FromScreenAndButtons;
-use embedded_hal::digital::v2::InputPin;
+use display::backlight::{Animation, Backlight};
+use display::dashboard::{Dashboard, DEFAULT_PAGE_DURATION_MS};
+use display::glyphs;
+use display::power::{PowerConfig, PowerPolicy, PowerState};
+use display::{FrameBuffer, GlyphDisplay, Message, TextDisplay};
 use embedded_hal::digital::v2::OutputPin;
-use lcd_1602_i2c::Lcd;
+use input::{ButtonEvent, GestureConfig};
+use inputs::Source;
+use lcd::Lcd1602;
+use locale::{Text, LANGUAGE};
+use pwm_buzzer::PwmBuzzer;
+use time_source::{Dcf77Decoder, ExternalClock};
 
 // Ensure we halt the program on panic (if we don't mention this crate it won't
 // be linked)
@@ -30,18 +50,22 @@ use panic_halt as _;
 
 // Time handling traits:
 use alarm::{Alarm, WeeklyDate};
+use buzzer::melodies;
+use buzzer::rtttl::Rtttl;
 use callbacks::{CallbackWriteText, StopperButton};
 use fugit::RateExtU32;
-use lcd::RainbowAnimation;
-use lcd::WriteCurrentDayAndTime;
+use led::Blinkable;
+use rp_pico::hal::gpio::FunctionUart;
 use rp_pico::hal::multicore::Multicore;
 use rp_pico::hal::rtc::{DateTime, DayOfWeek, RealTimeClock};
+use rp_pico::hal::uart::{self, UartPeripheral};
 use rp_pico::hal::Timer;
 
 use globals::ALLOCATOR;
 use globals::CORE1_STACK;
 use globals::LCD_ADDRESS;
 use globals::RGB_ADDRESS;
+use globals::{ExternalRtc, GPS_SYNC_PERIOD_MS, TIME_ZONE};
 
 use core_tasks::blink_led;
 
@@ -97,11 +121,28 @@ fn main() -> ! {
     );
 
     // Pins -------------------------------------------------------------------------------------------------------
-    let mut validate_button = pins.gpio1.into_pull_up_input();
-    let mut increment_button = pins.gpio5.into_pull_up_input();
+    let validate_pin = pins.gpio1.into_pull_up_input();
+    let increment_pin = pins.gpio5.into_pull_up_input();
     let mut led = pins.gpio9.into_push_pull_output();
-    let motion_sensor = pins.gpio16.into_pull_up_input();
-    let buzzer_pin = pins.gpio28.into_push_pull_output();
+    let motion_pin = pins.gpio16.into_pull_up_input();
+    // DCF77 receiver module
+    let radio_pin = pins.gpio17.into_pull_up_input();
+    // GPS receiver sending NMEA sentences, nothing is sent to it
+    let gps_pins = uart::Pins::default().rx(pins.gpio21.into_mode::<FunctionUart>());
+    let gps = UartPeripheral::new(pac.UART1, gps_pins, &mut pac.RESETS)
+        .enable(
+            uart::common_configs::_9600_8_N_1,
+            rp_pico::hal::Clock::freq(&clocks.peripheral_clock),
+        )
+        .unwrap();
+    // The buzzer is on the channel A of the PWM slice 6
+    let pwm_slices = rp_pico::hal::pwm::Slices::new(pac.PWM, &mut pac.RESETS);
+    let mut buzzer_pwm = pwm_slices.pwm6;
+    buzzer_pwm.channel_a.output_to(pins.gpio28);
+    let buzzer = PwmBuzzer::new(
+        buzzer_pwm,
+        rp_pico::hal::Clock::freq(&clocks.system_clock).to_Hz(),
+    );
 
     let sda_pin = pins.gpio12.into_mode::<rp_pico::hal::gpio::FunctionI2C>();
     let scl_pin = pins.gpio13.into_mode::<rp_pico::hal::gpio::FunctionI2C>();
@@ -116,25 +157,89 @@ fn main() -> ! {
         &mut pac.RESETS,
         &clocks.peripheral_clock,
     );
-    let mut lcd = Lcd::new(i2c, LCD_ADDRESS, RGB_ADDRESS, &mut delay).unwrap();
+    let mut lcd = GlyphDisplay::new(
+        Lcd1602::new(
+            i2c,
+            LCD_ADDRESS,
+            RGB_ADDRESS,
+            &mut delay,
+            rp_pico::hal::Clock::freq(&clocks.system_clock).to_Hz(),
+        )
+        .unwrap(),
+        glyphs::GLYPHS,
+    );
     let mut embedded_led = pins.led.into_push_pull_output();
 
-    // Ask for datetime ---------------------------------------------------------------------------------
-    lcd.clear(&mut delay).unwrap();
-    lcd.set_rgb(128, 128, 128).unwrap();
-    let date_time = DateTime::from_screen_and_buttons(
-        &mut lcd,
-        &mut delay,
-        &mut increment_button,
-        &mut validate_button,
+    // Inputs are read by interrupts from now on
+    let mut timer = Timer::new(pac.TIMER, &mut pac.RESETS);
+    inputs::init(
+        increment_pin,
+        validate_pin,
+        motion_pin,
+        radio_pin,
+        timer.alarm_0().unwrap(),
+    );
+    gps::init(gps);
+    let timer = Rc::new(timer);
+    let mut validate_button = GestureButton::new(Source::Validate, GestureConfig::default());
+    // Clicks are reported on release, a long press steps backwards
+    let mut increment_button = GestureButton::new(
+        Source::Increment,
+        GestureConfig {
+            double_press_ms: 0,
+            ..GestureConfig::default()
+        },
     );
+    let mut motion_sensor = MotionSensor::new();
+
+    // Ask for datetime ---------------------------------------------------------------------------------
+    // The external clock keeps the UTC time across power cuts, the user is
+    // only asked for the local time when it lost it too.
+    let mut external_rtc = ExternalRtc::new();
+    let mut drift = DriftTracker::new(storage::load().drift_ppb);
+    let stored_date_time = external_rtc
+        .read_time(lcd.inner_mut().i2c_mut())
+        .ok()
+        .flatten();
+    let date_time = match stored_date_time {
+        Some(stored) => {
+            drift.clock_set(stored.timestamp());
+            datetime::to_rtc_datetime(&stored)
+        }
+        None => {
+            lcd.clear().unwrap();
+            lcd.set_rgb(128, 128, 128).unwrap();
+            let default_date_time = clock::DateTime {
+                year: FIRST_YEAR,
+                month: 1,
+                day: 1,
+                hour: 0,
+                minute: 0,
+                second: 0,
+            };
+            let entered = DateTime::from_screen_and_buttons(
+                datetime::to_rtc_datetime(&default_date_time),
+                &mut lcd,
+                &mut increment_button,
+                &mut validate_button,
+            );
+            match entered {
+                Some(entered) => {
+                    let utc = TIME_ZONE.to_utc(&datetime::from_rtc_datetime(&entered));
+                    external_rtc.set_time(lcd.inner_mut().i2c_mut(), &utc).ok();
+                    drift.record(SyncSource::Manual, utc.timestamp(), None);
+                    datetime::to_rtc_datetime(&utc)
+                }
+                // Without anybody, the defaults are not worth keeping
+                None => datetime::to_rtc_datetime(&TIME_ZONE.to_utc(&default_date_time)),
+            }
+        }
+    };
 
     // Real Time Clock
-    let real_time_clock =
+    let mut real_time_clock =
         RealTimeClock::new(pac.RTC, clocks.rtc_clock, &mut pac.RESETS, date_time).unwrap();
 
-    let mut timer = Timer::new(pac.TIMER, &mut pac.RESETS);
-
     // // Start up the second core to blink the second LED
     // let mut mc = Multicore::new(&mut pac.PSM, &mut pac.PPB, &mut sio.fifo);
     // let cores = mc.cores();
@@ -147,27 +252,30 @@ fn main() -> ! {
     let rc_delay = Rc::new(RefCell::new(delay));
     let rc_lcd = Rc::new(RefCell::new(lcd));
     let rc_valid_button = Rc::new(RefCell::new(validate_button));
-    let rc_buzzer = Rc::new(RefCell::new(buzzer_pin));
+    let rc_buzzer = Rc::new(RefCell::new(buzzer));
 
     // Alarms ---------------------------------------------------------------
     let alarm = Alarm::new(
         WeeklyDate::new(DayOfWeek::Sunday, 18, 0, 0), // Green trash
-        ArrayString::<16>::from("Poubelle verte !").unwrap(),
+        ArrayString::<16>::from(LANGUAGE.text(Text::GreenBin)).unwrap(),
         6 * 3600, // 6 hours of uptime
-        0,
-        0,
         CallbackBuzzerAndWriteText::new(
-            ArrayString::<16>::from("Poubelle verte !").unwrap(),
+            Message::from(LANGUAGE.text(Text::TakeOutGreenBin)).unwrap(),
             Rc::clone(&rc_lcd),
             Rc::clone(&rc_delay),
             3 * 1000,
             Rc::clone(&rc_buzzer),
-            1 * 1000,
+            Rtttl::parse(melodies::GREEN_BIN).unwrap(),
             StopperButton::new(Rc::clone(&rc_valid_button)),
-            (0, 255, 0),
+            Animation::Pulse {
+                color: (0, 255, 0),
+                period_ms: 1000,
+                decay_ms: 600,
+            },
+            Rc::clone(&timer),
         ),
         CallbackWriteText::new(
-            ArrayString::<16>::from("Merci <3").unwrap(),
+            Message::from(LANGUAGE.text(Text::Thanks)).unwrap(),
             Rc::clone(&rc_lcd),
             Rc::clone(&rc_delay),
             5000,
@@ -176,22 +284,25 @@ fn main() -> ! {
 
     let alarm2 = Alarm::new(
         WeeklyDate::new(DayOfWeek::Wednesday, 18, 0, 0), // Yellow trash
-        ArrayString::<16>::from("Poubelle jaune !").unwrap(),
+        ArrayString::<16>::from(LANGUAGE.text(Text::YellowBin)).unwrap(),
         6 * 3600, // 6 hours of uptime
-        0,
-        0,
         CallbackBuzzerAndWriteText::new(
-            ArrayString::<16>::from("Poubelle jaune !").unwrap(),
+            Message::from(LANGUAGE.text(Text::TakeOutYellowBin)).unwrap(),
             Rc::clone(&rc_lcd),
             Rc::clone(&rc_delay),
             3 * 1000,
             Rc::clone(&rc_buzzer),
-            1 * 1000,
+            Rtttl::parse(melodies::YELLOW_BIN).unwrap(),
             StopperButton::new(Rc::clone(&rc_valid_button)),
-            (255, 255, 0),
+            Animation::Pulse {
+                color: (255, 255, 0),
+                period_ms: 1000,
+                decay_ms: 600,
+            },
+            Rc::clone(&timer),
         ),
         CallbackWriteText::new(
-            ArrayString::<16>::from("Merci <3").unwrap(),
+            Message::from(LANGUAGE.text(Text::Thanks)).unwrap(),
             Rc::clone(&rc_lcd),
             Rc::clone(&rc_delay),
             5000,
@@ -199,30 +310,133 @@ fn main() -> ! {
     );
 
     let mut alarm_manager = AlarmManager::new(vec![alarm, alarm2]);
+    let mut frame = FrameBuffer::new();
+    let mut backlight = Backlight::new(
+        Animation::Rainbow { period_ms: 10000 },
+        timer.get_counter() / 1000,
+    );
+    let mut dashboard = Dashboard::new(DEFAULT_PAGE_DURATION_MS, timer.get_counter() / 1000);
+    let mut power = PowerPolicy::new(PowerConfig::default(), timer.get_counter() / 1000);
+    let mut radio = Dcf77Decoder::new();
+    let mut last_gps_sync_ms: Option<u64> = None;
 
     loop {
-        let now = match real_time_clock.now() {
-            Ok(value) => value,
+        let utc = match real_time_clock.now() {
+            Ok(value) => datetime::from_rtc_datetime(&value),
             Err(_err) => {
                 continue; // We skip a loop
             }
         };
-        (*rc_lcd).borrow_mut().animate_rainbow(10000, &mut timer);
-        (*rc_lcd).borrow_mut().write_current_day_and_time(&now);
+        // The drift is compensated by steps of a second
+        let step_sec = drift.correction(utc.timestamp());
+        if step_sec != 0 {
+            let corrected =
+                clock::DateTime::from_timestamp(utc.timestamp().wrapping_add_signed(step_sec));
+            real_time_clock
+                .set_datetime(datetime::to_rtc_datetime(&corrected))
+                .ok();
+            continue;
+        }
+        let local = TIME_ZONE.to_local(&utc);
+        let now = datetime::to_rtc_datetime(&local);
+        let now_ms = timer.get_counter() / 1000;
+        // The radio and GPS times replace the one of both clocks
+        while let Some(edge) = inputs::next_edge(Source::Radio) {
+            if let Some(received) = radio.edge(edge.active, edge.at_ms) {
+                let elapsed_sec = (now_ms.saturating_sub(received.at_ms) / 1000) as u32;
+                datetime::sync_clocks(
+                    &mut real_time_clock,
+                    &mut external_rtc,
+                    (*rc_lcd).borrow_mut().inner_mut().i2c_mut(),
+                    &mut drift,
+                    SyncSource::Radio,
+                    &clock::DateTime::from_timestamp(received.utc().timestamp() + elapsed_sec),
+                );
+            }
+        }
+        if let Some(received) = gps::take_time() {
+            if last_gps_sync_ms.map_or(true, |ms| now_ms - ms >= GPS_SYNC_PERIOD_MS) {
+                let elapsed_sec = (now_ms.saturating_sub(received.at_ms) / 1000) as u32;
+                datetime::sync_clocks(
+                    &mut real_time_clock,
+                    &mut external_rtc,
+                    (*rc_lcd).borrow_mut().inner_mut().i2c_mut(),
+                    &mut drift,
+                    SyncSource::Gps,
+                    &clock::DateTime::from_timestamp(received.datetime.timestamp() + elapsed_sec),
+                );
+                last_gps_sync_ms = Some(now_ms);
+            }
+        }
+        let motion = motion_sensor.is_active();
+        let increment_events = increment_button.poll();
+        let validate_events = (*rc_valid_button).borrow_mut().poll();
+        // The press waking the display up does nothing else
+        let was_asleep = power.state(now_ms) == PowerState::Asleep;
+        if motion || !increment_events.is_empty() || !validate_events.is_empty() {
+            power.presence(now_ms);
+            if was_asleep {
+                dashboard.restart(now_ms);
+            }
+        }
+        let button_events_used = !was_asleep;
+        // Ringing alarms must be seen
+        power.set_alarm_pending(alarm_manager.is_any_ringing(&now));
+
+        // Draw the screen in memory, only the changes are sent to the LCD
+        frame.clear().ok();
+        backlight.set_level(power.level(now_ms, now.hour));
+        backlight.update(&mut frame, now_ms).ok();
+        if power.state(now_ms) != PowerState::Asleep {
+            dashboard
+                .render(
+                    &mut frame,
+                    &local,
+                    &utc,
+                    now_ms,
+                    &alarm_summary(&alarm_manager, &now),
+                    &drift,
+                )
+                .ok();
+        }
+        frame.flush((*rc_lcd).borrow_mut().deref_mut()).ok();
         alarm_manager.rearm_all(&now);
         // Trigger if movement is detected
-        if let Some(true) = motion_sensor.is_high().ok() {
+        if motion {
             led.set_high().ok();
             (*rc_delay).borrow_mut().delay_ms(100);
             led.set_low().ok();
             (*rc_delay).borrow_mut().delay_ms(100);
-            alarm_manager.trigger_all(&now);
+            // Failing alarms are retried on the next loop, blink the LED so the
+            // failure can be noticed.
+            for failure in alarm_manager.trigger_all(&now) {
+                let blink_count = if failure.degraded { 3 } else { 1 };
+                for _ in 0..blink_count {
+                    led.blink((*rc_delay).borrow_mut().deref_mut(), 50);
+                }
+            }
+            // Alarm callbacks draw on the LCD directly
+            frame.invalidate();
+            dashboard.restart(timer.get_counter() / 1000);
         }
-        (*rc_delay).borrow_mut().delay_ms(20);
-        // Clear the display
-        (*rc_lcd)
-            .borrow_mut()
-            .clear((*rc_delay).borrow_mut().deref_mut())
+        // A long press opens the alarm settings
+        if button_events_used && increment_events.contains(&ButtonEvent::LongPress) {
+            alarm_editor::edit_alarms(
+                &mut alarm_manager,
+                &mut frame,
+                (*rc_lcd).borrow_mut().deref_mut(),
+                &mut increment_button,
+                (*rc_valid_button).borrow_mut().deref_mut(),
+            )
             .ok();
+            dashboard.restart(timer.get_counter() / 1000);
+            power.presence(timer.get_counter() / 1000);
+        }
+        // A click acknowledges the missed alarms, other presses are only used
+        // by the alarms
+        if button_events_used && validate_events.contains(&ButtonEvent::ShortPress) {
+            alarm_manager.clear_missed();
+        }
+        (*rc_delay).borrow_mut().delay_ms(20);
     }
 }
@@ -0,0 +1,50 @@
+use buzzer::pwm::pwm_config;
+use buzzer::Buzzer;
+use embedded_hal::PwmPin;
+use rp_pico::hal::pwm::{FreeRunning, Slice, SliceId};
+
+#[derive(Debug)]
+pub enum BuzzerError {
+    /// The PWM slice cannot output this frequency.
+    UnsupportedFrequency(u16),
+}
+
+/// Buzzer driven by the channel A of a PWM slice.
+pub struct PwmBuzzer<S: SliceId> {
+    slice: Slice<S, FreeRunning>,
+    system_clock_hz: u32,
+}
+
+impl<S: SliceId> PwmBuzzer<S> {
+    /// The buzzer pin must already be routed to the channel A of the slice.
+    pub fn new(mut slice: Slice<S, FreeRunning>, system_clock_hz: u32) -> Self {
+        slice.default_config();
+        slice.channel_a.set_duty(0);
+        slice.channel_a.enable();
+        slice.enable();
+        Self {
+            slice,
+            system_clock_hz,
+        }
+    }
+}
+
+impl<S: SliceId> Buzzer for PwmBuzzer<S> {
+    type Error = BuzzerError;
+
+    fn start_tone(&mut self, frequency_hz: u16) -> Result<(), Self::Error> {
+        let config = pwm_config(self.system_clock_hz, frequency_hz as u32)
+            .ok_or(BuzzerError::UnsupportedFrequency(frequency_hz))?;
+        self.slice.set_div_int(config.div_int);
+        self.slice.set_div_frac(config.div_frac);
+        self.slice.set_top(config.top);
+        self.slice.set_counter(0);
+        self.slice.channel_a.set_duty(config.half_duty());
+        Ok(())
+    }
+
+    fn stop(&mut self) -> Result<(), Self::Error> {
+        self.slice.channel_a.set_duty(0);
+        Ok(())
+    }
+}
@@ -0,0 +1,88 @@
+//! Settings kept in the last sector of the flash across reboots.
+//!
+//! `memory.x` leaves the sector out of the program. The flash cannot be read
+//! while it is written, so the writing code runs from RAM with interrupts
+//! disabled and the second core idle, then restores the fast reading mode of
+//! the boot loader.
+
+use rp_pico::hal::rom_data;
+
+const XIP_BASE: u32 = 0x1000_0000;
+const FLASH_SIZE: u32 = 2048 * 1024;
+const SECTOR_SIZE: u32 = 4096;
+const PAGE_SIZE: usize = 256;
+const OFFSET: u32 = FLASH_SIZE - SECTOR_SIZE;
+// Erases by 64 KiB blocks where possible, like the Pico SDK
+const BLOCK_SIZE: u32 = 65536;
+const BLOCK_ERASE_COMMAND: u8 = 0xD8;
+// Erased flash reads as ones, which must not look like settings
+const MAGIC: u32 = 0x5452_4131;
+
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct Settings {
+    /// Estimate of `clock::drift::DriftTracker`
+    pub drift_ppb: Option<i32>,
+}
+
+pub fn load() -> Settings {
+    let words = unsafe { &*((XIP_BASE + OFFSET) as *const [u32; 3]) };
+    if words[0] != MAGIC {
+        return Settings::default();
+    }
+    Settings {
+        drift_ppb: (words[1] != 0).then_some(words[2] as i32),
+    }
+}
+
+/// Replaces the settings, which takes a few tens of milliseconds.
+pub fn save(settings: &Settings) {
+    let mut page = [0xFF; PAGE_SIZE];
+    let words = [
+        MAGIC,
+        settings.drift_ppb.is_some() as u32,
+        settings.drift_ppb.unwrap_or(0) as u32,
+    ];
+    for (chunk, word) in page.chunks_exact_mut(4).zip(words) {
+        chunk.copy_from_slice(&word.to_le_bytes());
+    }
+    // The ROM functions are looked up while the flash can still be read
+    let rom = RomFunctions {
+        connect_internal_flash: rom_data::connect_internal_flash::ptr(),
+        flash_exit_xip: rom_data::flash_exit_xip::ptr(),
+        flash_range_erase: rom_data::flash_range_erase::ptr(),
+        flash_range_program: rom_data::flash_range_program::ptr(),
+        flash_flush_cache: rom_data::flash_flush_cache::ptr(),
+    };
+    let mut boot2 = [0u32; 64];
+    cortex_m::interrupt::free(|_| unsafe {
+        rom_data::memcpy44(boot2.as_mut_ptr(), XIP_BASE as *const u32, 256);
+        // Thumb code
+        let boot2 = (boot2.as_ptr() as *const u8).add(1);
+        write_page(&rom, boot2, &page);
+    });
+}
+
+struct RomFunctions {
+    connect_internal_flash: unsafe extern "C" fn(),
+    flash_exit_xip: unsafe extern "C" fn(),
+    flash_range_erase: unsafe extern "C" fn(u32, usize, u32, u8),
+    flash_range_program: unsafe extern "C" fn(u32, *const u8, usize),
+    flash_flush_cache: unsafe extern "C" fn(),
+}
+
+#[inline(never)]
+#[link_section = ".data.ram_func"]
+unsafe fn write_page(rom: &RomFunctions, boot2: *const u8, page: &[u8; PAGE_SIZE]) {
+    (rom.connect_internal_flash)();
+    (rom.flash_exit_xip)();
+    (rom.flash_range_erase)(
+        OFFSET,
+        SECTOR_SIZE as usize,
+        BLOCK_SIZE,
+        BLOCK_ERASE_COMMAND,
+    );
+    (rom.flash_range_program)(OFFSET, page.as_ptr(), PAGE_SIZE);
+    (rom.flash_flush_cache)();
+    let boot2: extern "C" fn() = core::mem::transmute(boot2);
+    boot2();
+}